@@ -0,0 +1,188 @@
+//! `#[derive(FromValue, IntoValue)]` for `rusty_scheme::convert`.
+//!
+//! A struct with named fields is an association list of its fields, a tuple
+//! struct a list of its fields and a unit struct the symbol of its name. An
+//! enum variant is the same, tagged with the variant name: `(Circle 1.5)`,
+//! `(Rect (w . 1) (h . 2))`, or just `Empty` for a unit variant.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, Generics, Ident};
+
+#[proc_macro_derive(FromValue)]
+pub fn derive_from_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let body = match &input.data {
+        Data::Struct(data) if matches!(data.fields, Fields::Unit) => quote! {
+            ::rusty_scheme::convert::symbol(value, stringify!(#name))?;
+            Ok(#name)
+        },
+        Data::Struct(data) => from_fields(quote!(#name), &data.fields, quote!(value)),
+        Data::Enum(data) => {
+            let arms = data.variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                let tag = ident.to_string();
+                let construct =
+                    from_fields(quote!(#name::#ident), &variant.fields, quote!(&fields));
+                quote!(#tag => { #construct })
+            });
+            quote! {
+                let (tag, fields) = ::rusty_scheme::convert::tagged(value)?;
+                match tag.as_str() {
+                    #(#arms)*
+                    _ => Err(::rusty_scheme::convert::unknown_variant(&tag, stringify!(#name))),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(&input, "FromValue cannot be derived for unions")
+                .to_compile_error()
+                .into()
+        }
+    };
+    let generics = bound(&input.generics, quote!(::rusty_scheme::convert::FromValue));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::rusty_scheme::convert::FromValue for #name #ty_generics #where_clause {
+            fn from_value(
+                value: &::rusty_scheme::value::Value,
+            ) -> ::std::result::Result<Self, ::rusty_scheme::SchemeError> {
+                #body
+            }
+        }
+    }
+    .into()
+}
+
+#[proc_macro_derive(IntoValue)]
+pub fn derive_into_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, value) = into_fields(&data.fields, None, &name.to_string());
+            quote! {
+                let #name #pattern = self;
+                #value
+            }
+        }
+        Data::Enum(data) => {
+            let arms = data.variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                let (pattern, value) = into_fields(
+                    &variant.fields,
+                    Some(&ident.to_string()),
+                    &ident.to_string(),
+                );
+                quote!(#name::#ident #pattern => #value,)
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(&input, "IntoValue cannot be derived for unions")
+                .to_compile_error()
+                .into()
+        }
+    };
+    let generics = bound(&input.generics, quote!(::rusty_scheme::convert::IntoValue));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::rusty_scheme::convert::IntoValue for #name #ty_generics #where_clause {
+            fn into_value(self) -> ::rusty_scheme::value::Value {
+                #body
+            }
+        }
+    }
+    .into()
+}
+
+// Builds `path` from `value`, a list of the fields or an alist of them.
+fn from_fields(path: TokenStream2, fields: &Fields, value: TokenStream2) -> TokenStream2 {
+    match fields {
+        Fields::Named(named) => {
+            let fields = named.named.iter().map(|field| {
+                let ident = field.ident.as_ref().unwrap();
+                let key = ident.to_string();
+                quote!(#ident: ::rusty_scheme::convert::field(&fields, #key)?)
+            });
+            quote! {
+                let fields = ::rusty_scheme::convert::alist(#value)?;
+                Ok(#path { #(#fields),* })
+            }
+        }
+        Fields::Unnamed(unnamed) => {
+            let len = unnamed.unnamed.len();
+            let fields =
+                (0..len).map(|i| quote!(::rusty_scheme::convert::element(&items[#i], #i)?));
+            quote! {
+                let items = ::rusty_scheme::convert::list(#value, #len)?;
+                Ok(#path(#(#fields),*))
+            }
+        }
+        Fields::Unit => quote! {
+            ::rusty_scheme::convert::list(#value, 0)?;
+            Ok(#path)
+        },
+    }
+}
+
+// The pattern destructuring `fields` and the value built from them, tagged
+// with `tag` for an enum variant. A unit struct or variant is the symbol
+// `name`.
+fn into_fields(fields: &Fields, tag: Option<&str>, name: &str) -> (TokenStream2, TokenStream2) {
+    let tag = tag.map(|tag| quote!(::rusty_scheme::value::Value::symbol(#tag)));
+    match fields {
+        Fields::Named(named) => {
+            let idents: Vec<_> = named
+                .named
+                .iter()
+                .map(|f| f.ident.clone().unwrap())
+                .collect();
+            let keys = idents.iter().map(|ident| ident.to_string());
+            let tag = tag.into_iter();
+            // The fields are bound by their own names, so the list is not
+            // named in a way a field could be.
+            (
+                quote!({ #(#idents),* }),
+                quote! {{
+                    let mut __rusty_scheme_items = vec![#(#tag),*];
+                    #(__rusty_scheme_items
+                        .extend(::rusty_scheme::convert::into_field(#keys, #idents));)*
+                    ::rusty_scheme::value::Value::list(__rusty_scheme_items)
+                }},
+            )
+        }
+        Fields::Unnamed(unnamed) => {
+            let idents: Vec<Ident> = (0..unnamed.unnamed.len())
+                .map(|i| format_ident!("field{}", i))
+                .collect();
+            let items = tag.into_iter().chain(
+                idents
+                    .iter()
+                    .map(|ident| quote!(::rusty_scheme::convert::IntoValue::into_value(#ident))),
+            );
+            (
+                quote!((#(#idents),*)),
+                quote!(::rusty_scheme::value::Value::list(vec![#(#items),*])),
+            )
+        }
+        Fields::Unit => (
+            quote!(),
+            quote!(::rusty_scheme::value::Value::symbol(#name)),
+        ),
+    }
+}
+
+fn bound(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
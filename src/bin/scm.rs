@@ -1,20 +1,115 @@
+use rusty_scheme::backtrace::StackFrame;
+use rusty_scheme::limits;
 use rusty_scheme::value::Value;
-use rusty_scheme::{eval_program, parse_program, SchemeError};
+use rusty_scheme::{
+    eval_program_in_order, eval_program_traced, eval_program_with_diagrams, parse_program,
+    EvaluationOrder, ParseError, SchemeError,
+};
+use std::{env, fs, process};
 
-fn run(src: &str) -> Result<Value, SchemeError> {
-    let parsed = parse_program(src);
-    let program = match parsed {
-        Ok((_, p)) => Ok(p),
-        Err(e) => {
-            dbg!(e);
-            Err(SchemeError::ParseError)
+#[derive(Default)]
+struct Options {
+    order: EvaluationOrder,
+    trace: bool,
+    diagram: Option<DiagramFormat>,
+}
+
+enum DiagramFormat {
+    Ascii,
+    Dot,
+}
+
+fn run(src: &str, options: &Options) -> Result<Value, SchemeError> {
+    let (_, program) =
+        parse_program(src).map_err(|e| SchemeError::ParseError(ParseError::new(src, e)))?;
+    let order = options.order;
+    match &options.diagram {
+        None if options.trace => {
+            eval_program_traced(&program, order, &mut |step| println!("{}", step))
         }
-    }?;
-    eval_program(&program)
+        Some(format) => eval_program_with_diagrams(&program, order, &mut |diagram| match format {
+            DiagramFormat::Ascii => print!("{}", diagram.to_ascii()),
+            DiagramFormat::Dot => print!("{}", diagram.to_dot()),
+        }),
+        None => eval_program_in_order(&program, order),
+    }
 }
 
 fn main() {
-    let newton_src = "
+    // Usage: scm [--normal] [--trace | --diagram | --dot] [FILE]. Without a file the
+    // Newton example runs.
+    let mut options = Options::default();
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--normal" => options.order = EvaluationOrder::Normal,
+            "--applicative" => options.order = EvaluationOrder::Applicative,
+            "--trace" => options.trace = true,
+            "--diagram" => options.diagram = Some(DiagramFormat::Ascii),
+            "--dot" => options.diagram = Some(DiagramFormat::Dot),
+            _ => path = Some(arg),
+        }
+    }
+    if options.trace && options.diagram.is_some() {
+        eprintln!("--trace cannot be combined with --diagram or --dot");
+        eprintln!("Usage: scm [--normal] [--trace | --diagram | --dot] [FILE]");
+        process::exit(2);
+    }
+    let src = match path {
+        Some(path) => match fs::read_to_string(&path) {
+            Ok(src) => src,
+            Err(e) => {
+                eprintln!("Cannot read {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => NEWTON_SRC.to_owned(),
+    };
+    // Ctrl-C stops the running evaluation instead of killing the process. The
+    // evaluation only notices between two steps, so a second Ctrl-C before it
+    // has, e.g. while it waits for input, exits.
+    let token = limits::cancellation_token();
+    let on_interrupt = move || {
+        if token.is_cancelled() {
+            process::exit(130);
+        }
+        token.cancel();
+    };
+    if let Err(e) = ctrlc::set_handler(on_interrupt) {
+        eprintln!("Cannot handle Ctrl-C: {}", e);
+    }
+    match run(&src, &options) {
+        Ok(value) => println!("Result: {}", value),
+        Err(e) => {
+            eprintln!("Error [{}]: {}", e.code(), e.to_string_with_sources());
+            if let SchemeError::ParseError(parse) = &e {
+                let (line, col) = parse.span.line_col(&src);
+                eprintln!("    at {}:{}", line, col);
+            }
+            print_backtrace(&src, e.backtrace());
+            process::exit(1);
+        }
+    }
+}
+
+const MAX_BACKTRACE_FRAMES: usize = 20;
+
+fn print_backtrace(src: &str, backtrace: &[StackFrame]) {
+    for frame in backtrace.iter().take(MAX_BACKTRACE_FRAMES) {
+        match frame.span {
+            Some(span) => {
+                let (line, col) = span.line_col(src);
+                eprintln!("    at {} ({}:{})", frame, line, col);
+            }
+            None => eprintln!("    at {}", frame),
+        }
+    }
+    if backtrace.len() > MAX_BACKTRACE_FRAMES {
+        eprintln!("    ... {} more", backtrace.len() - MAX_BACKTRACE_FRAMES);
+    }
+}
+
+const NEWTON_SRC: &str = "
     (L3
         (define sqrt (lambda (x) (sqrt-iter 1 x)))
 
@@ -41,6 +136,3 @@ fn main() {
             (average guess (/ x guess))))
 
         (sqrt 2))";
-    let _fact_src = "(L3 (define fact (lambda (n) (if (= n 0) 1 (* n (fact (- n 1)))))) (fact 5))";
-    println!("Result: {:#?}", run(newton_src));
-}
@@ -1,9 +1,13 @@
-use crate::value::SExpression;
+use crate::backtrace::Span;
+use crate::value::Value;
+use std::fmt;
 
 #[derive(Debug, Clone)]
 pub struct Application {
     pub operator: Box<ConstituentExpression>,
-    pub operands: Vec<Box<ConstituentExpression>>,
+    pub operands: Vec<ConstituentExpression>,
+    // `None` for applications built by the evaluator or by `eval`.
+    pub span: Option<Span>,
 }
 
 #[derive(Debug, Clone)]
@@ -16,13 +20,42 @@ pub struct If {
 #[derive(Debug, Clone)]
 pub struct Procedure {
     pub args: Vec<VariableDeclaration>,
-    pub body: Vec<Box<ConstituentExpression>>,
+    pub body: Vec<ConstituentExpression>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Let {
     pub bindings: Vec<(VariableDeclaration, Box<ConstituentExpression>)>,
-    pub body: Vec<Box<ConstituentExpression>>,
+    pub body: Vec<ConstituentExpression>,
+}
+
+/// `let-values`; `let*-values` and `receive` are parsed into nested forms of
+/// this.
+#[derive(Debug, Clone)]
+pub struct LetValues {
+    pub bindings: Vec<(Vec<VariableDeclaration>, Box<ConstituentExpression>)>,
+    pub body: Vec<ConstituentExpression>,
+}
+
+/// `delay`, or `delay-force` when `lazy` is set.
+#[derive(Debug, Clone)]
+pub struct Delay {
+    pub expr: Box<ConstituentExpression>,
+    pub lazy: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct CondClause {
+    pub test: Option<Box<ConstituentExpression>>,
+    pub body: Vec<ConstituentExpression>,
+    pub arrow: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Guard {
+    pub var: VariableDeclaration,
+    pub clauses: Vec<CondClause>,
+    pub body: Vec<ConstituentExpression>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,29 +67,106 @@ pub struct Boolean(pub bool);
 #[derive(Debug, Clone)]
 pub struct Str(pub String);
 
-#[derive(Debug, Clone)]
-pub enum PrimitiveOperation {
-    Add,
-    Sub,
-    Mul,
-    Div,
-    Equal,
-    LessThan,
-    LessThanOrEqual,
-    GreaterThan,
-    GreaterThanOrEqual,
-    Not,
-    And,
-    Or,
-    IsEq,
-    Cons,
-    Car,
-    Cdr,
-    List,
-    IsPair,
-    IsNumber,
-    IsBoolean,
-    IsSymbol,
+macro_rules! primitive_operations {
+    ($($variant:ident => $name:literal),* $(,)?) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum PrimitiveOperation {
+            $($variant),*
+        }
+
+        impl PrimitiveOperation {
+            /// The names of all primitive operations.
+            pub const NAMES: &'static [&'static str] = &[$($name),*];
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(PrimitiveOperation::$variant => $name),*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<PrimitiveOperation> {
+                match name {
+                    $($name => Some(PrimitiveOperation::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+primitive_operations! {
+    Add => "+",
+    Sub => "-",
+    Mul => "*",
+    Div => "/",
+    Equal => "=",
+    LessThan => "<",
+    LessThanOrEqual => "<=",
+    GreaterThan => ">",
+    GreaterThanOrEqual => ">=",
+    Not => "not",
+    And => "and",
+    Or => "or",
+    IsEq => "eq?",
+    Cons => "cons",
+    Car => "car",
+    Cdr => "cdr",
+    List => "list",
+    IsPair => "pair?",
+    IsNumber => "number?",
+    IsBoolean => "boolean?",
+    IsSymbol => "symbol?",
+    Display => "display",
+    Write => "write",
+    WriteString => "write-string",
+    Newline => "newline",
+    WriteChar => "write-char",
+    ReadChar => "read-char",
+    PeekChar => "peek-char",
+    ReadLine => "read-line",
+    ReadString => "read-string",
+    CurrentInputPort => "current-input-port",
+    CurrentOutputPort => "current-output-port",
+    CurrentErrorPort => "current-error-port",
+    OpenInputFile => "open-input-file",
+    OpenOutputFile => "open-output-file",
+    OpenInputString => "open-input-string",
+    OpenOutputString => "open-output-string",
+    GetOutputString => "get-output-string",
+    ClosePort => "close-port",
+    CloseInputPort => "close-input-port",
+    CloseOutputPort => "close-output-port",
+    CallWithOutputString => "call-with-output-string",
+    WithOutputToFile => "with-output-to-file",
+    Read => "read",
+    Eval => "eval",
+    InteractionEnvironment => "interaction-environment",
+    SchemeReportEnvironment => "scheme-report-environment",
+    NullEnvironment => "null-environment",
+    IsEnvironment => "environment?",
+    Error => "error",
+    Raise => "raise",
+    RaiseContinuable => "raise-continuable",
+    WithExceptionHandler => "with-exception-handler",
+    IsErrorObject => "error-object?",
+    ErrorObjectMessage => "error-object-message",
+    ErrorObjectIrritants => "error-object-irritants",
+    IsFileError => "file-error?",
+    IsReadError => "read-error?",
+    CallCC => "call/cc",
+    CallWithCurrentContinuation => "call-with-current-continuation",
+    DynamicWind => "dynamic-wind",
+    MakePromise => "make-promise",
+    IsPromise => "promise?",
+    Force => "force",
+    StreamCar => "stream-car",
+    StreamCdr => "stream-cdr",
+    Breakpoint => "breakpoint",
+    Values => "values",
+    CallWithValues => "call-with-values",
+    IsForeign => "foreign?",
+    EofObject => "eof-object",
+    IsEofObject => "eof-object?",
 }
 
 #[derive(Debug, Clone)]
@@ -71,9 +181,13 @@ pub enum ConstituentExpression {
     If(If),
     Procedure(Procedure),
     Let(Let),
+    LetValues(LetValues),
+    Guard(Guard),
+    Delay(Delay),
     Number(Number),
     Boolean(Boolean),
-    Literal(SExpression),
+    Str(Str),
+    Literal(Value),
     PrimitiveOperation(PrimitiveOperation),
     VariableReference(VariableReference),
 }
@@ -84,9 +198,16 @@ pub struct Define {
     pub val: ConstituentExpression,
 }
 
+#[derive(Debug, Clone)]
+pub struct DefineValues {
+    pub vars: Vec<String>,
+    pub val: ConstituentExpression,
+}
+
 #[derive(Debug, Clone)]
 pub enum Expression {
     Define(Define),
+    DefineValues(DefineValues),
     ConstituentExpression(ConstituentExpression),
 }
 
@@ -94,3 +215,88 @@ pub enum Expression {
 pub struct Program {
     pub exps: Vec<Expression>,
 }
+
+// Unparses an expression back into Scheme syntax, e.g. for tracing.
+impl fmt::Display for ConstituentExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstituentExpression::Applic(applic) => {
+                write!(f, "({}", applic.operator)?;
+                for operand in &applic.operands {
+                    write!(f, " {}", operand)?;
+                }
+                write!(f, ")")
+            }
+            ConstituentExpression::If(ifexp) => {
+                write!(f, "(if {} {} {})", ifexp.cond, ifexp.then, ifexp.alt)
+            }
+            ConstituentExpression::Procedure(proc) => {
+                write!(f, "(lambda ")?;
+                fmt_formals(&proc.args, f)?;
+                fmt_body(&proc.body, f)
+            }
+            ConstituentExpression::Let(letexp) => {
+                write!(f, "(let (")?;
+                for (i, (var, val)) in letexp.bindings.iter().enumerate() {
+                    let sep = if i > 0 { " " } else { "" };
+                    write!(f, "{}({} {})", sep, var.0, val)?;
+                }
+                write!(f, ")")?;
+                fmt_body(&letexp.body, f)
+            }
+            ConstituentExpression::LetValues(letexp) => {
+                write!(f, "(let-values (")?;
+                for (i, (formals, val)) in letexp.bindings.iter().enumerate() {
+                    write!(f, "{}(", if i > 0 { " " } else { "" })?;
+                    fmt_formals(formals, f)?;
+                    write!(f, " {})", val)?;
+                }
+                write!(f, ")")?;
+                fmt_body(&letexp.body, f)
+            }
+            ConstituentExpression::Guard(guard) => {
+                write!(f, "(guard ({}", guard.var.0)?;
+                for clause in &guard.clauses {
+                    write!(f, " (")?;
+                    match &clause.test {
+                        None => write!(f, "else")?,
+                        Some(test) if clause.arrow => write!(f, "{} =>", test)?,
+                        Some(test) => write!(f, "{}", test)?,
+                    }
+                    fmt_body(&clause.body, f)?;
+                }
+                write!(f, ")")?;
+                fmt_body(&guard.body, f)
+            }
+            ConstituentExpression::Delay(delay) if delay.lazy => {
+                write!(f, "(delay-force {})", delay.expr)
+            }
+            ConstituentExpression::Delay(delay) => write!(f, "(delay {})", delay.expr),
+            ConstituentExpression::Number(n) => write!(f, "{}", Value::Number(n.0)),
+            ConstituentExpression::Boolean(b) => write!(f, "{}", Value::Boolean(b.0)),
+            ConstituentExpression::Str(s) => write!(f, "{}", Value::String(s.0.clone())),
+            ConstituentExpression::Literal(value @ Value::SExpression(_)) => {
+                write!(f, "'{}", value)
+            }
+            ConstituentExpression::Literal(value) => write!(f, "{}", value),
+            ConstituentExpression::PrimitiveOperation(primop) => write!(f, "{}", primop.name()),
+            ConstituentExpression::VariableReference(var) => write!(f, "{}", var.0),
+        }
+    }
+}
+
+fn fmt_formals(formals: &[VariableDeclaration], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, var) in formals.iter().enumerate() {
+        write!(f, "{}{}", if i > 0 { " " } else { "" }, var.0)?;
+    }
+    write!(f, ")")
+}
+
+// Writes the body expressions and the closing parenthesis of a form.
+fn fmt_body(body: &[ConstituentExpression], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for cexp in body {
+        write!(f, " {}", cexp)?;
+    }
+    write!(f, ")")
+}
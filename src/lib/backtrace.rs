@@ -0,0 +1,102 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Where an application appears in the source text it was parsed from.
+///
+/// Spans are byte offsets from the start of that text, which `source`
+/// identifies; `offsets` and `line_col` resolve them against it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    source: SourceId,
+    start: usize,
+    end: usize,
+}
+
+/// A text that was parsed. Every parse of a text gets a new one, so spans
+/// from an earlier text can be told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceId(u64);
+
+impl SourceId {
+    pub(crate) fn new() -> SourceId {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        SourceId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Span {
+    pub(crate) fn new(source: SourceId, start: usize, end: usize) -> Span {
+        Span { source, start, end }
+    }
+
+    pub fn source(&self) -> SourceId {
+        self.source
+    }
+
+    /// The byte range of the span in `src`.
+    pub fn offsets(&self, src: &str) -> Range<usize> {
+        self.start.min(src.len())..self.end.min(src.len())
+    }
+
+    /// The 1-based line and column where the span starts in `src`.
+    pub fn line_col(&self, src: &str) -> (usize, usize) {
+        let start = self.offsets(src).start;
+        let before = src.get(..start).unwrap_or(src);
+        let line = before.matches('\n').count() + 1;
+        let col = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+        (line, col)
+    }
+}
+
+/// An active procedure application when an error was raised.
+///
+/// `name` is the variable or primitive the procedure was called through, if
+/// any, and `tail_calls` the number of tail calls collapsed into this frame.
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    pub name: Option<String>,
+    pub span: Option<Span>,
+    pub args: Vec<String>,
+    pub tail_calls: usize,
+}
+
+const MAX_ARG_LEN: usize = 30;
+
+impl StackFrame {
+    pub(crate) fn new(
+        name: Option<String>,
+        span: Option<Span>,
+        args: impl Iterator<Item = String>,
+        tail_calls: usize,
+    ) -> StackFrame {
+        StackFrame {
+            name,
+            span,
+            args: args.map(abbreviate).collect(),
+            tail_calls,
+        }
+    }
+}
+
+fn abbreviate(arg: String) -> String {
+    match arg.char_indices().nth(MAX_ARG_LEN) {
+        Some((end, _)) => format!("{}...", &arg[..end]),
+        None => arg,
+    }
+}
+
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}", self.name.as_deref().unwrap_or("#<procedure>"))?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        write!(f, ")")?;
+        match self.tail_calls {
+            0 => Ok(()),
+            1 => write!(f, " [1 tail call]"),
+            n => write!(f, " [{} tail calls]", n),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::ast::PrimitiveOperation;
+use crate::value::Value;
+use crate::SchemeError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What a program may reach outside of its own computation. The initial
+/// environment only has the primitives the capabilities install, and
+/// referring to any other fails with `SchemeError::PermissionDenied`, as does
+/// using an installed one on the console or on a file outside `read_root`.
+/// `eval` runs under the same capabilities. There is no `load` primitive, so
+/// `eval` is the only way a program can run code it builds or reads.
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    // String ports, and ports handed to the program by the host.
+    pub ports: bool,
+    // The current input, output and error ports.
+    pub console: bool,
+    pub read_files: bool,
+    // If set, only the files below this directory can be read.
+    pub read_root: Option<PathBuf>,
+    pub write_files: bool,
+}
+
+impl Capabilities {
+    /// Computation only, without any ports.
+    pub fn pure() -> Capabilities {
+        Capabilities {
+            ports: false,
+            console: false,
+            read_files: false,
+            read_root: None,
+            write_files: false,
+        }
+    }
+
+    /// In-memory string ports and ports from the host, but no console or files.
+    pub fn no_io() -> Capabilities {
+        Capabilities {
+            ports: true,
+            ..Capabilities::pure()
+        }
+    }
+
+    /// `no_io`, plus reading the files below `root`.
+    pub fn read_only_fs(root: impl Into<PathBuf>) -> Capabilities {
+        Capabilities {
+            read_files: true,
+            read_root: Some(root.into()),
+            ..Capabilities::no_io()
+        }
+    }
+
+    pub fn full() -> Capabilities {
+        Capabilities {
+            ports: true,
+            console: true,
+            read_files: true,
+            read_root: None,
+            write_files: true,
+        }
+    }
+
+    /// Whether the initial environment has `primop`.
+    pub(crate) fn installs(&self, primop: PrimitiveOperation) -> bool {
+        match primop {
+            PrimitiveOperation::Display
+            | PrimitiveOperation::Write
+            | PrimitiveOperation::WriteString
+            | PrimitiveOperation::WriteChar
+            | PrimitiveOperation::ReadString
+            | PrimitiveOperation::Newline
+            | PrimitiveOperation::ReadChar
+            | PrimitiveOperation::PeekChar
+            | PrimitiveOperation::ReadLine
+            | PrimitiveOperation::Read => self.ports || self.console,
+            PrimitiveOperation::CurrentInputPort
+            | PrimitiveOperation::CurrentOutputPort
+            | PrimitiveOperation::CurrentErrorPort => self.console,
+            PrimitiveOperation::OpenInputString
+            | PrimitiveOperation::OpenOutputString
+            | PrimitiveOperation::GetOutputString
+            | PrimitiveOperation::ClosePort
+            | PrimitiveOperation::CloseInputPort
+            | PrimitiveOperation::CloseOutputPort
+            | PrimitiveOperation::CallWithOutputString => self.ports,
+            PrimitiveOperation::OpenInputFile => self.ports && self.read_files,
+            PrimitiveOperation::OpenOutputFile | PrimitiveOperation::WithOutputToFile => {
+                self.ports && self.write_files
+            }
+            _ => true,
+        }
+    }
+
+    /// Fails unless applying `primop` to `args` is allowed. A primitive value
+    /// can come from another interpreter, so this checks that `primop` is
+    /// installed as well.
+    pub(crate) fn check(
+        &self,
+        primop: PrimitiveOperation,
+        args: &[Value],
+    ) -> Result<(), SchemeError> {
+        let allowed = self.installs(primop)
+            && match primop {
+                // The port argument comes after the datum or count, if there
+                // is one. Without it, they use the current port.
+                PrimitiveOperation::Display
+                | PrimitiveOperation::Write
+                | PrimitiveOperation::WriteString
+                | PrimitiveOperation::WriteChar
+                | PrimitiveOperation::ReadString => self.port_argument(args.get(1)),
+                PrimitiveOperation::Newline
+                | PrimitiveOperation::ReadChar
+                | PrimitiveOperation::PeekChar
+                | PrimitiveOperation::ReadLine
+                | PrimitiveOperation::Read => self.port_argument(args.first()),
+                _ => true,
+            };
+        if allowed {
+            Ok(())
+        } else {
+            Err(denied(primop))
+        }
+    }
+
+    /// The file `open-input-file` opens for `path`. Below a read root, that is
+    /// the canonical path that was found to be inside it, so that replacing a
+    /// link after the check cannot make it open another file.
+    pub(crate) fn input_file(&self, path: &str) -> Result<PathBuf, SchemeError> {
+        match &self.read_root {
+            Some(root) => {
+                canonical_below(root, path).ok_or_else(|| denied(PrimitiveOperation::OpenInputFile))
+            }
+            None => Ok(PathBuf::from(path)),
+        }
+    }
+
+    // Whether an I/O primitive may use `port`, or the current port when
+    // there is none. Anything that is not a port is left for the primitive
+    // to reject.
+    fn port_argument(&self, port: Option<&Value>) -> bool {
+        match port {
+            Some(_) => self.ports,
+            None => self.console,
+        }
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Capabilities {
+        Capabilities::full()
+    }
+}
+
+pub(crate) fn denied(primop: PrimitiveOperation) -> SchemeError {
+    SchemeError::PermissionDenied {
+        procedure: primop.name().to_owned(),
+    }
+}
+
+// `path` with symbolic links and `..` resolved, if it is below `root`. A file
+// that cannot be resolved counts as outside, so a denial does not tell whether
+// it exists.
+fn canonical_below(root: &Path, path: &str) -> Option<PathBuf> {
+    match (fs::canonicalize(root), fs::canonicalize(path)) {
+        (Ok(root), Ok(path)) if path.starts_with(&root) => Some(path),
+        _ => None,
+    }
+}
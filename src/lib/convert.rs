@@ -0,0 +1,496 @@
+//! Conversions between Rust types and Scheme values.
+//!
+//! Sequences and tuples are lists, maps are association lists, `None` is `#f`
+//! and an `Err` is an error object. `#[derive(FromValue, IntoValue)]` converts
+//! a struct with named fields to an alist, a tuple struct to a list, and an
+//! enum variant to a list tagged with the variant name, or just the name.
+//!
+//! A field that is `None` is left out of the alist rather than stored as
+//! `#f`, so that `Some(false)` in an `Option<bool>` field survives a round
+//! trip. Elsewhere `#f` reads as `None` only when `T` cannot read it, so a
+//! bare `Option<bool>` that was `None` reads back as `Some(false)`.
+
+use crate::shared::{SendSync, Shared};
+use crate::value::{ErrorObject, Foreign, SExpression, Value};
+use crate::SchemeError;
+use std::any::{self, Any};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+pub use rusty_scheme_derive::{FromValue, IntoValue};
+
+/// Rust types that can be read from a Scheme value, e.g. the arguments of a
+/// function registered with `Interpreter::register_typed`.
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self, SchemeError>;
+
+    /// The value of a struct field missing from its alist, if it may be.
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Rust types that can be turned into a Scheme value.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+
+    /// Whether a struct field holding `self` is left out of its alist.
+    fn is_absent(&self) -> bool {
+        false
+    }
+
+    /// The result of a typed host function returning `self`; an `Err` is
+    /// raised instead of returned, or passed on as it is if it already is a
+    /// `SchemeError`.
+    fn into_result(self) -> Result<Value, SchemeError>
+    where
+        Self: Sized,
+    {
+        Ok(self.into_value())
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Result<Value, SchemeError> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<f64, SchemeError> {
+        match value {
+            Value::Number(n) => Ok(*n),
+            _ => Err(mismatch(value, "a number")),
+        }
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: &Value) -> Result<f32, SchemeError> {
+        f64::from_value(value).map(|n| n as f32)
+    }
+}
+
+impl IntoValue for f32 {
+    fn into_value(self) -> Value {
+        Value::Number(self.into())
+    }
+}
+
+// Numbers are floats, so integers convert only when they are whole and in
+// range.
+macro_rules! integers {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(value: &Value) -> Result<$ty, SchemeError> {
+                    let n = f64::from_value(value)?;
+                    if !n.is_finite() || n.fract() != 0.0 {
+                        return Err(mismatch(value, "an integer"));
+                    }
+                    // `MAX as f64` may round up to the power of two above
+                    // it, so the bound is that power of two, exclusive.
+                    if n < <$ty>::MIN as f64 || n >= <$ty>::MAX as f64 + 1.0 {
+                        return Err(mismatch(
+                            value,
+                            concat!("a number in the range of ", stringify!($ty)),
+                        ));
+                    }
+                    Ok(n as $ty)
+                }
+            }
+
+            impl IntoValue for $ty {
+                fn into_value(self) -> Value {
+                    Value::Number(self as f64)
+                }
+            }
+        )*
+    };
+}
+
+integers!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<bool, SchemeError> {
+        match value {
+            Value::Boolean(b) => Ok(*b),
+            _ => Err(mismatch(value, "a boolean")),
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl FromValue for char {
+    fn from_value(value: &Value) -> Result<char, SchemeError> {
+        match value {
+            Value::Char(c) => Ok(*c),
+            _ => Err(mismatch(value, "a character")),
+        }
+    }
+}
+
+impl IntoValue for char {
+    fn into_value(self) -> Value {
+        Value::Char(self)
+    }
+}
+
+// Symbols convert to their names too, so that alist keys can be read as
+// strings.
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<String, SchemeError> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            _ => match value.as_symbol() {
+                Some(name) => Ok(name.to_owned()),
+                None => Err(mismatch(value, "a string")),
+            },
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::String(self.to_owned())
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Void
+    }
+}
+
+/// A shared handle to a foreign object of type `T`.
+impl<T: Any + SendSync> FromValue for Shared<T> {
+    fn from_value(value: &Value) -> Result<Shared<T>, SchemeError> {
+        match value {
+            Value::Foreign(foreign) => foreign
+                .downcast()
+                .ok_or_else(|| mismatch(value, &format!("a foreign {}", any::type_name::<T>()))),
+            _ => Err(mismatch(
+                value,
+                &format!("a foreign {}", any::type_name::<T>()),
+            )),
+        }
+    }
+}
+
+impl IntoValue for Foreign {
+    fn into_value(self) -> Value {
+        Value::Foreign(self)
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value) -> Result<Vec<T>, SchemeError> {
+        let items = value
+            .list_items()
+            .ok_or_else(|| mismatch(value, "a list"))?;
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| element(item, i))
+            .collect()
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::list(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Option<T>, SchemeError> {
+        match T::from_value(value) {
+            Ok(value) => Ok(Some(value)),
+            Err(_) if matches!(value, Value::Boolean(false)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn missing() -> Option<Option<T>> {
+        Some(None)
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(value) => value.into_value(),
+            None => Value::Boolean(false),
+        }
+    }
+
+    fn is_absent(&self) -> bool {
+        self.is_none()
+    }
+}
+
+impl<K, V> FromValue for HashMap<K, V>
+where
+    K: FromValue + Eq + Hash,
+    V: FromValue,
+{
+    fn from_value(value: &Value) -> Result<HashMap<K, V>, SchemeError> {
+        alist(value)?
+            .iter()
+            .map(|(key, val)| {
+                let context = key.to_string();
+                let key = K::from_value(key).map_err(|e| in_context(e, "key"))?;
+                let val = V::from_value(val).map_err(|e| in_context(e, &context))?;
+                Ok((key, val))
+            })
+            .collect()
+    }
+}
+
+impl<K: IntoValue, V: IntoValue> IntoValue for HashMap<K, V> {
+    fn into_value(self) -> Value {
+        Value::list(
+            self.into_iter()
+                .map(|(key, val)| Value::cons(key.into_value(), val.into_value()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: FromValue, E: FromValue> FromValue for Result<T, E> {
+    fn from_value(value: &Value) -> Result<Result<T, E>, SchemeError> {
+        match value {
+            Value::Error(err) => Ok(Err(E::from_value(&Value::String(err.message.clone()))?)),
+            _ => Ok(Ok(T::from_value(value)?)),
+        }
+    }
+}
+
+impl<T: IntoValue, E: fmt::Display + 'static> IntoValue for Result<T, E> {
+    fn into_value(self) -> Value {
+        match self {
+            Ok(value) => value.into_value(),
+            Err(e) => error_object(e),
+        }
+    }
+
+    fn into_result(self) -> Result<Value, SchemeError> {
+        match self {
+            Ok(value) => Ok(value.into_value()),
+            // An error from `Interpreter::apply` keeps its kind, so that going
+            // over a limit or being interrupted cannot be caught by `guard`.
+            Err(e) => match scheme_error(e) {
+                Ok(e) => Err(e),
+                Err(e) => Err(SchemeError::Raise(error_object(e))),
+            },
+        }
+    }
+}
+
+fn scheme_error<E: 'static>(e: E) -> Result<SchemeError, E> {
+    let mut e = Some(e);
+    match (&mut e as &mut dyn Any).downcast_mut::<Option<SchemeError>>() {
+        Some(scheme_error) => Ok(scheme_error.take().unwrap()),
+        None => Err(e.unwrap()),
+    }
+}
+
+fn error_object(e: impl fmt::Display) -> Value {
+    Value::Error(Box::from(ErrorObject {
+        message: e.to_string(),
+        irritants: vec![],
+        origin: None,
+    }))
+}
+
+macro_rules! one {
+    ($ty:ident) => {
+        1
+    };
+}
+
+// Tuples are lists of the same length.
+macro_rules! tuples {
+    ($(($($ty:ident),+)),* $(,)?) => {
+        $(
+            impl<$($ty: FromValue),+> FromValue for ($($ty,)+) {
+                fn from_value(value: &Value) -> Result<Self, SchemeError> {
+                    let items = list(value, 0 $(+ one!($ty))+)?;
+                    let mut items = items.iter().enumerate();
+                    Ok(($({
+                        let (i, item) = items.next().unwrap();
+                        element::<$ty>(item, i)?
+                    },)+))
+                }
+            }
+
+            #[allow(non_snake_case)]
+            impl<$($ty: IntoValue),+> IntoValue for ($($ty,)+) {
+                fn into_value(self) -> Value {
+                    let ($($ty,)+) = self;
+                    Value::list(vec![$($ty.into_value()),+])
+                }
+            }
+        )*
+    };
+}
+
+tuples! {
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+}
+
+/// The argument list of a typed host function: a tuple of `FromValue` types.
+pub trait FromArgs: Sized {
+    const ARITY: usize;
+
+    fn from_args(args: &[Value]) -> Result<Self, SchemeError>;
+}
+
+// Callers check the number of arguments against `ARITY` first.
+macro_rules! from_args_tuples {
+    ($(($($ty:ident),*)),* $(,)?) => {
+        $(
+            impl<$($ty: FromValue),*> FromArgs for ($($ty,)*) {
+                const ARITY: usize = 0 $(+ one!($ty))*;
+
+                #[allow(unused_variables, unused_mut)]
+                fn from_args(args: &[Value]) -> Result<Self, SchemeError> {
+                    let mut args = args.iter();
+                    Ok(($($ty::from_value(args.next().unwrap_or(&Value::Void))?,)*))
+                }
+            }
+        )*
+    };
+}
+
+from_args_tuples! {
+    (),
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+}
+
+// The helpers below are used by the code `#[derive(FromValue)]` generates.
+
+/// The items of `value`, which must be a list of `len` elements.
+pub fn list(value: &Value, len: usize) -> Result<Vec<Value>, SchemeError> {
+    match value.list_items() {
+        Some(items) if items.len() == len => Ok(items),
+        _ => Err(mismatch(value, &format!("a list of {} elements", len))),
+    }
+}
+
+/// Converts the element at `index` of a list.
+pub fn element<T: FromValue>(value: &Value, index: usize) -> Result<T, SchemeError> {
+    T::from_value(value).map_err(|e| in_context(e, &format!("element {}", index)))
+}
+
+/// The pairs of an association list.
+pub fn alist(value: &Value) -> Result<Vec<(Value, Value)>, SchemeError> {
+    let items = value
+        .list_items()
+        .ok_or_else(|| mismatch(value, "an association list"))?;
+    items
+        .into_iter()
+        .map(|item| match &item {
+            Value::SExpression(sexpr) => match &**sexpr {
+                SExpression::Compound(key, val) => Ok((key.clone(), val.clone())),
+                _ => Err(mismatch(&item, "a pair")),
+            },
+            _ => Err(mismatch(&item, "a pair")),
+        })
+        .collect()
+}
+
+/// Converts the field `name` of a struct read from an association list.
+pub fn field<T: FromValue>(fields: &[(Value, Value)], name: &str) -> Result<T, SchemeError> {
+    match fields.iter().find(|(key, _)| key.as_symbol() == Some(name)) {
+        Some((_, val)) => T::from_value(val).map_err(|e| in_context(e, &format!("field {}", name))),
+        None => T::missing().ok_or_else(|| {
+            let alist = fields
+                .iter()
+                .map(|(key, val)| Value::cons(key.clone(), val.clone()))
+                .collect();
+            mismatch(&Value::list(alist), &format!("a field {}", name))
+        }),
+    }
+}
+
+/// The alist entry of the field `name` of a struct, unless it is absent.
+pub fn into_field<T: IntoValue>(name: &str, value: T) -> Option<Value> {
+    if value.is_absent() {
+        None
+    } else {
+        Some(Value::cons(Value::symbol(name), value.into_value()))
+    }
+}
+
+/// Splits an enum value into its variant name and the list of its fields.
+pub fn tagged(value: &Value) -> Result<(String, Value), SchemeError> {
+    if let Some(name) = value.as_symbol() {
+        return Ok((name.to_owned(), Value::nil()));
+    }
+    if let Value::SExpression(sexpr) = value {
+        if let SExpression::Compound(tag, fields) = &**sexpr {
+            if let Some(name) = tag.as_symbol() {
+                return Ok((name.to_owned(), fields.clone()));
+            }
+        }
+    }
+    Err(mismatch(value, "a symbol or a tagged list"))
+}
+
+/// Checks that `value` is the symbol `name`, as a unit struct is.
+pub fn symbol(value: &Value, name: &str) -> Result<(), SchemeError> {
+    match value.as_symbol() {
+        Some(symbol) if symbol == name => Ok(()),
+        _ => Err(mismatch(value, &format!("the symbol {}", name))),
+    }
+}
+
+pub fn unknown_variant(name: &str, ty: &str) -> SchemeError {
+    mismatch(&Value::symbol(name), &format!("a variant of {}", ty))
+}
+
+fn in_context(mut e: SchemeError, context: &str) -> SchemeError {
+    if let SchemeError::TypeMismatch { path, .. } = &mut e {
+        path.insert(0, context.to_owned());
+    }
+    e
+}
+
+fn mismatch(value: &Value, expected: &str) -> SchemeError {
+    SchemeError::type_mismatch(expected, value)
+}
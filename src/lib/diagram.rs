@@ -0,0 +1,278 @@
+use crate::ast::ConstituentExpression;
+use crate::env::Environment;
+use crate::shared::Shared;
+use crate::value::{Closure, Value};
+use std::fmt::Write;
+
+/// A snapshot of the environment frames visible at one point of evaluation,
+/// taken at a `(breakpoint)` call or at the end of the program.
+///
+/// Besides the global environment and the environments made by
+/// `scheme-report-environment` and friends, each drawn as one frame, there is
+/// a frame for every procedure call in progress, binding its parameters to
+/// the arguments. A call in tail position replaces the frame of its caller.
+#[derive(Debug, Clone)]
+pub struct EnvironmentDiagram {
+    pub label: Option<String>,
+    pub frames: Vec<DiagramFrame>,
+    pub closures: Vec<DiagramClosure>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiagramFrame {
+    pub name: String,
+    pub bindings: Vec<DiagramBinding>,
+    // The index of the enclosing frame, for the frame of a procedure call.
+    pub parent: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiagramBinding {
+    pub name: String,
+    pub value: String,
+    // The index into `closures` when the value is a closure.
+    pub closure: Option<usize>,
+}
+
+/// A closure object, whose `parent` is the index of the frame it was created in.
+#[derive(Debug, Clone)]
+pub struct DiagramClosure {
+    pub params: Vec<String>,
+    pub body: String,
+    pub parent: usize,
+}
+
+/// The frame the environment model makes for applying a closure. Procedure
+/// bodies are evaluated by substitution instead, so the evaluator only keeps
+/// these for diagrams.
+#[derive(Debug)]
+pub(crate) struct CallFrame {
+    pub(crate) bindings: Vec<(String, Argument)>,
+    // The environment the closure was created in.
+    pub(crate) parent: Shared<Environment>,
+}
+
+/// An argument of a call: a value, or in normal order the operand expression
+/// that was substituted unevaluated.
+#[derive(Debug)]
+pub(crate) enum Argument {
+    Value(Value),
+    Operand(ConstituentExpression),
+}
+
+impl EnvironmentDiagram {
+    /// The frames visible from `env`, and those of `calls`, outermost first.
+    pub(crate) fn snapshot(
+        env: &Environment,
+        calls: &[Shared<CallFrame>],
+        label: Option<String>,
+    ) -> EnvironmentDiagram {
+        let mut builder = Builder {
+            roots: vec![],
+            diagram: EnvironmentDiagram {
+                label,
+                frames: vec![],
+                closures: vec![],
+            },
+        };
+        builder.frame(env);
+        for call in calls {
+            builder.call(call);
+        }
+        builder.diagram
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph environment {\n    node [shape=record];\n");
+        if let Some(label) = &self.label {
+            let _ = writeln!(out, "    label=\"{}\";", escape(label));
+        }
+        for (i, frame) in self.frames.iter().enumerate() {
+            let mut fields = vec![escape(&frame.name)];
+            for (j, binding) in frame.bindings.iter().enumerate() {
+                let value = match binding.closure {
+                    Some(_) => String::new(),
+                    None => escape(&binding.value),
+                };
+                fields.push(format!("<b{}> {}: {}\\l", j, escape(&binding.name), value));
+            }
+            let _ = writeln!(out, "    frame{} [label=\"{{{}}}\"];", i, fields.join("|"));
+            if let Some(parent) = frame.parent {
+                let _ = writeln!(out, "    frame{} -> frame{};", i, parent);
+            }
+            for (j, binding) in frame.bindings.iter().enumerate() {
+                if let Some(closure) = binding.closure {
+                    let _ = writeln!(out, "    frame{}:b{} -> closure{};", i, j, closure);
+                }
+            }
+        }
+        for (i, closure) in self.closures.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    closure{} [shape=box, style=rounded, label=\"p: {}\\lb: {}\\l\"];",
+                i,
+                escape(&closure.params.join(" ")),
+                escape(&closure.body)
+            );
+            let _ = writeln!(
+                out,
+                "    closure{} -> frame{} [style=dashed];",
+                i, closure.parent
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_ascii(&self) -> String {
+        let mut out = String::new();
+        if let Some(label) = &self.label {
+            let _ = writeln!(out, "== {} ==", label);
+        }
+        for frame in &self.frames {
+            let title = match frame.parent {
+                Some(parent) => format!("{} (parent {})", frame.name, self.frames[parent].name),
+                None => frame.name.clone(),
+            };
+            let lines: Vec<_> = frame
+                .bindings
+                .iter()
+                .map(|binding| match binding.closure {
+                    Some(closure) => format!("{}: -> closure{}", binding.name, closure),
+                    None => format!("{}: {}", binding.name, binding.value),
+                })
+                .collect();
+            let width = lines
+                .iter()
+                .map(|line| line.chars().count())
+                .chain(Some(title.chars().count()))
+                .max()
+                .unwrap_or(0);
+            let border = format!("+{}+", "-".repeat(width + 2));
+            let _ = writeln!(out, "{}", border);
+            let _ = writeln!(out, "| {:width$} |", title, width = width);
+            let _ = writeln!(out, "{}", border);
+            for line in &lines {
+                let _ = writeln!(out, "| {:width$} |", line, width = width);
+            }
+            let _ = writeln!(out, "{}", border);
+        }
+        for (i, closure) in self.closures.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "closure{}: params ({}), body {}, parent {}",
+                i,
+                closure.params.join(" "),
+                closure.body,
+                self.frames[closure.parent].name
+            );
+        }
+        out
+    }
+}
+
+struct Builder {
+    // The root of each frame in `diagram.frames`, which identifies it, or null
+    // for the frame of a call.
+    roots: Vec<*const Environment>,
+    diagram: EnvironmentDiagram,
+}
+
+impl Builder {
+    fn frame(&mut self, env: &Environment) -> usize {
+        let root = env.root();
+        if let Some(i) = self.roots.iter().position(|r| *r == root) {
+            return i;
+        }
+        let index = self.roots.len();
+        self.roots.push(root);
+        self.diagram.frames.push(DiagramFrame {
+            name: frame_name(index),
+            bindings: vec![],
+            parent: None,
+        });
+        // A redefinition replaces the value in place, like `define` does in a
+        // frame.
+        let mut bindings: Vec<(&str, &Value)> = vec![];
+        for (name, value) in env.bindings().into_iter().rev() {
+            match bindings.iter_mut().find(|(n, _)| *n == name) {
+                Some(binding) => binding.1 = value,
+                None => bindings.push((name, value)),
+            }
+        }
+        for (name, value) in bindings {
+            let binding = self.binding(name, value);
+            self.diagram.frames[index].bindings.push(binding);
+        }
+        index
+    }
+
+    fn call(&mut self, call: &CallFrame) {
+        let parent = self.frame(&call.parent);
+        let bindings = call
+            .bindings
+            .iter()
+            .map(|(name, arg)| match arg {
+                Argument::Value(value) => self.binding(name, value),
+                Argument::Operand(operand) => DiagramBinding {
+                    name: name.clone(),
+                    value: operand.to_string(),
+                    closure: None,
+                },
+            })
+            .collect();
+        self.roots.push(std::ptr::null());
+        self.diagram.frames.push(DiagramFrame {
+            name: frame_name(self.diagram.frames.len()),
+            bindings,
+            parent: Some(parent),
+        });
+    }
+
+    fn binding(&mut self, name: &str, value: &Value) -> DiagramBinding {
+        let closure = match value {
+            Value::Closure(closure) => Some(self.closure(closure)),
+            _ => None,
+        };
+        DiagramBinding {
+            name: name.to_owned(),
+            value: value.to_string(),
+            closure,
+        }
+    }
+
+    fn closure(&mut self, closure: &Closure) -> usize {
+        let parent = self.frame(&closure.env);
+        let body = closure
+            .body
+            .iter()
+            .map(|cexp| cexp.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.diagram.closures.push(DiagramClosure {
+            params: closure.params.iter().map(|vd| vd.0.clone()).collect(),
+            body,
+            parent,
+        });
+        self.diagram.closures.len() - 1
+    }
+}
+
+fn frame_name(index: usize) -> String {
+    if index == 0 {
+        "GE".to_owned()
+    } else {
+        format!("E{}", index)
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if matches!(c, '"' | '\\' | '{' | '}' | '|' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -1,26 +1,163 @@
+use crate::ast::PrimitiveOperation;
+use crate::capability::{self, Capabilities};
+use crate::shared::Shared;
+use crate::suggest::similar_names;
 use crate::value::Value;
 use crate::SchemeError;
 
 #[derive(Debug, Clone)]
 pub enum Environment {
-    Empty,
-    NonEmpty(String, Value, Box<Environment>),
+    // Without bindings, and with the primitives the capabilities install.
+    Initial(Shared<Capabilities>),
+    NonEmpty(String, Value, Shared<Environment>),
 }
 
 impl Environment {
+    /// An environment without bindings, in which a program can only refer to
+    /// the primitives `capabilities` install.
+    pub(crate) fn initial(capabilities: &Capabilities) -> Shared<Environment> {
+        Shared::new(Environment::Initial(Shared::new(capabilities.clone())))
+    }
+
+    /// The initial environment this one extends, without its bindings.
+    pub(crate) fn without_bindings(&self) -> Shared<Environment> {
+        Shared::new(Environment::Initial(self.capabilities().clone()))
+    }
+
+    /// The value of `primop`, unless the initial environment lacks it.
+    pub(crate) fn primitive(&self, primop: PrimitiveOperation) -> Result<Value, SchemeError> {
+        if self.capabilities().installs(primop) {
+            Ok(Value::PrimitiveOperation(primop))
+        } else {
+            Err(capability::denied(primop))
+        }
+    }
+
+    fn capabilities(&self) -> &Shared<Capabilities> {
+        let mut cur = self;
+        loop {
+            match cur {
+                Environment::Initial(capabilities) => return capabilities,
+                Environment::NonEmpty(_, _, next_env) => cur = next_env,
+            }
+        }
+    }
+
     pub fn apply(&self, var: &str) -> Result<&Value, SchemeError> {
-        match self {
-            Environment::Empty => Err(SchemeError::UndefinedVariable(format!(
-                "Undefined variable: {}",
-                var
-            ))),
-            Environment::NonEmpty(v, val, next_env) => {
-                if var == v {
-                    Ok(val)
-                } else {
-                    next_env.apply(var)
-                }
+        self.lookup(var).ok_or_else(|| self.undefined(var))
+    }
+
+    /// The value bound to `var`, without the suggestions `apply` makes when
+    /// there is none.
+    pub fn lookup(&self, var: &str) -> Option<&Value> {
+        let mut cur = self;
+        while let Environment::NonEmpty(v, val, next_env) = cur {
+            if var == v {
+                return Some(val);
+            }
+            cur = next_env;
+        }
+        None
+    }
+
+    pub(crate) fn undefined(&self, var: &str) -> SchemeError {
+        let bound = self.bindings().into_iter().map(|(name, _)| name);
+        let capabilities = self.capabilities();
+        let primitives = PrimitiveOperation::NAMES.iter().copied().filter(|name| {
+            PrimitiveOperation::from_name(name).is_some_and(|primop| capabilities.installs(primop))
+        });
+        SchemeError::UndefinedVariable {
+            name: var.to_owned(),
+            suggestions: similar_names(var, bound.chain(primitives)),
+        }
+    }
+
+    /// `env` with `var` bound to `value` in place of any binding of `var` it
+    /// has, so that redefining a name does not lengthen the chain.
+    pub(crate) fn rebind(
+        env: &Shared<Environment>,
+        var: &str,
+        value: Value,
+    ) -> Shared<Environment> {
+        let mut above = vec![];
+        let mut cur = env;
+        while let Environment::NonEmpty(v, val, next_env) = &**cur {
+            if v == var {
+                let rest = above
+                    .into_iter()
+                    .rev()
+                    .fold(next_env.clone(), |env, (v, val)| {
+                        Shared::new(Environment::NonEmpty(v, val, env))
+                    });
+                return Shared::new(Environment::NonEmpty(var.to_owned(), value, rest));
+            }
+            above.push((v.clone(), val.clone()));
+            cur = next_env;
+        }
+        Shared::new(Environment::NonEmpty(var.to_owned(), value, env.clone()))
+    }
+
+    /// The bindings of the environment, most recent first.
+    pub(crate) fn bindings(&self) -> Vec<(&str, &Value)> {
+        let mut bindings = vec![];
+        let mut cur = self;
+        while let Environment::NonEmpty(var, val, next_env) = cur {
+            bindings.push((var.as_str(), val));
+            cur = next_env;
+        }
+        bindings
+    }
+
+    /// The initial environment this one extends, which identifies its frame.
+    pub(crate) fn root(&self) -> *const Environment {
+        let mut cur = self;
+        while let Environment::NonEmpty(_, _, next_env) = cur {
+            cur = next_env;
+        }
+        cur
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_replaces_the_old_binding() {
+        let mut env = Environment::initial(&Capabilities::full());
+        for i in 0..100 {
+            env = Environment::rebind(&env, "x", Value::Number(i as f64));
+            env = Environment::rebind(&env, "y", Value::Boolean(true));
+        }
+        assert_eq!(env.bindings().len(), 2);
+        assert!(matches!(env.lookup("x"), Some(Value::Number(n)) if *n == 99.0));
+    }
+
+    #[test]
+    fn rebinding_keeps_the_other_bindings() {
+        let mut env = Environment::initial(&Capabilities::full());
+        for var in ["a", "b", "c"] {
+            env = Environment::rebind(&env, var, Value::Number(1.0));
+        }
+        env = Environment::rebind(&env, "b", Value::Number(2.0));
+        let names: Vec<_> = env.bindings().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["b", "c", "a"]);
+        assert!(env.lookup("a").is_some() && env.lookup("c").is_some());
+    }
+
+    #[test]
+    fn undefined_variables_suggest_close_names() {
+        let env = Environment::rebind(
+            &Environment::initial(&Capabilities::full()),
+            "sqrt-iter",
+            Value::Void,
+        );
+        match env.apply("sqrt-itr") {
+            Err(SchemeError::UndefinedVariable { suggestions, .. }) => {
+                assert_eq!(suggestions, ["sqrt-iter"])
             }
+            other => panic!("unexpected {:?}", other),
         }
+        assert!(env.lookup("sqrt-itr").is_none());
     }
 }
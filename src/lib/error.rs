@@ -0,0 +1,373 @@
+use crate::backtrace::{SourceId, Span, StackFrame};
+use crate::limits::Limit;
+use crate::shared::Shared;
+use crate::value::{ErrorObject, Value};
+use nom::error::ErrorKind;
+use std::{error, fmt, io};
+
+#[derive(Debug, Clone)]
+pub enum SchemeError {
+    ParseError(ParseError),
+    /// `suggestions` are bound names close to `name`, closest first.
+    UndefinedVariable {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    /// A value that is not a procedure was applied.
+    BadProcedure(Value),
+    /// A continuation was resumed outside of the host function application it
+    /// was captured in.
+    ContinuationEscape,
+    /// `actual` is not `expected`, e.g. "a number". `path` says where in the
+    /// argument it was found when converting a value, e.g. `["element 1"]`.
+    TypeMismatch {
+        procedure: Option<String>,
+        expected: String,
+        actual: Box<Value>,
+        path: Vec<String>,
+    },
+    ArgumentMismatch {
+        procedure: Option<String>,
+        expected: Arity,
+        actual: usize,
+    },
+    /// A `define-values` or `let-values` got the wrong number of values.
+    ValuesMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    IoError {
+        message: String,
+        source: Option<Shared<io::Error>>,
+    },
+    PermissionDenied {
+        procedure: String,
+    },
+    ReadError(String),
+    SyntaxError(String),
+    Raise(Value),
+    LimitExceeded(Limit),
+    Interrupted,
+    EmptyProgram,
+    /// An error that escaped evaluation, with the procedure applications that
+    /// were active when it was raised, innermost first.
+    Traced {
+        error: Box<SchemeError>,
+        backtrace: Vec<StackFrame>,
+    },
+}
+
+/// The number of arguments a procedure takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+    AtMost(usize),
+}
+
+/// Where and why the program text could not be parsed, as reported by nom.
+/// `found` is the character parsing stopped at, or `None` at the end of the
+/// input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ErrorKind,
+    pub span: Span,
+    pub found: Option<char>,
+}
+
+impl ParseError {
+    /// The error nom reported parsing `src`, e.g. with `parse_program(src)`.
+    pub fn new(src: &str, e: nom::Err<nom::error::Error<&str>>) -> ParseError {
+        match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => ParseError::at(e.code, src, e.input),
+            nom::Err::Incomplete(_) => ParseError::at(ErrorKind::Complete, src, ""),
+        }
+    }
+
+    /// A failure at the start of `rest`, the unparsed end of `src`.
+    pub(crate) fn at(kind: ErrorKind, src: &str, rest: &str) -> ParseError {
+        let rest = rest.trim_start();
+        let start = src.len() - rest.len();
+        ParseError {
+            kind,
+            span: Span::new(SourceId::new(), start, start),
+            found: rest.chars().next(),
+        }
+    }
+}
+
+impl SchemeError {
+    pub(crate) fn type_mismatch(expected: &str, actual: &Value) -> SchemeError {
+        SchemeError::TypeMismatch {
+            procedure: None,
+            expected: expected.to_owned(),
+            actual: Box::from(actual.clone()),
+            path: vec![],
+        }
+    }
+
+    pub(crate) fn arity(expected: Arity, actual: usize) -> SchemeError {
+        SchemeError::ArgumentMismatch {
+            procedure: None,
+            expected,
+            actual,
+        }
+    }
+
+    pub(crate) fn io(message: String, source: io::Error) -> SchemeError {
+        SchemeError::IoError {
+            message,
+            source: Some(Shared::new(source)),
+        }
+    }
+
+    /// Names `name` as the procedure whose arguments were wrong, unless one is
+    /// named already.
+    pub(crate) fn in_procedure(mut self, name: &str) -> SchemeError {
+        if let SchemeError::TypeMismatch { procedure, .. }
+        | SchemeError::ArgumentMismatch { procedure, .. } = &mut self
+        {
+            procedure.get_or_insert_with(|| name.to_owned());
+        }
+        self
+    }
+
+    /// A short name for the kind of error that stays the same across
+    /// releases, unlike the messages.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SchemeError::ParseError(_) => "parse-error",
+            SchemeError::UndefinedVariable { .. } => "undefined-variable",
+            SchemeError::BadProcedure(_) => "bad-procedure",
+            SchemeError::ContinuationEscape => "continuation-escape",
+            SchemeError::TypeMismatch { .. } => "type-mismatch",
+            SchemeError::ArgumentMismatch { .. } => "argument-mismatch",
+            SchemeError::ValuesMismatch { .. } => "values-mismatch",
+            SchemeError::IoError { .. } => "io-error",
+            SchemeError::PermissionDenied { .. } => "permission-denied",
+            SchemeError::ReadError(_) => "read-error",
+            SchemeError::SyntaxError(_) => "syntax-error",
+            SchemeError::Raise(_) => "raise",
+            SchemeError::LimitExceeded(_) => "limit-exceeded",
+            SchemeError::Interrupted => "interrupted",
+            SchemeError::EmptyProgram => "empty-program",
+            SchemeError::Traced { error, .. } => error.code(),
+        }
+    }
+
+    pub(crate) fn is_catchable(&self) -> bool {
+        match self {
+            SchemeError::ParseError(_)
+            | SchemeError::EmptyProgram
+            | SchemeError::LimitExceeded(_)
+            | SchemeError::Interrupted => false,
+            // Raised inside a procedure a host function applied.
+            SchemeError::Traced { error, .. } => error.is_catchable(),
+            _ => true,
+        }
+    }
+
+    /// The error without its backtrace.
+    pub fn root(&self) -> &SchemeError {
+        match self {
+            SchemeError::Traced { error, .. } => error,
+            _ => self,
+        }
+    }
+
+    /// The active procedure applications when the error was raised, innermost
+    /// first; empty when it was not raised by evaluation.
+    pub fn backtrace(&self) -> &[StackFrame] {
+        match self {
+            SchemeError::Traced { backtrace, .. } => backtrace,
+            _ => &[],
+        }
+    }
+
+    /// Where in the source the error happened: the parse position, or the
+    /// innermost application in the backtrace that has a span.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            SchemeError::ParseError(e) => Some(e.span),
+            _ => self.backtrace().iter().find_map(|frame| frame.span),
+        }
+    }
+
+    pub(crate) fn with_backtrace(self, backtrace: Vec<StackFrame>) -> SchemeError {
+        match self {
+            // An error from a procedure applied by a host function already
+            // has the frames below the host function.
+            SchemeError::Traced {
+                error,
+                backtrace: mut inner,
+            } => {
+                inner.extend(backtrace);
+                SchemeError::Traced {
+                    error,
+                    backtrace: inner,
+                }
+            }
+            error => SchemeError::Traced {
+                error: Box::from(error),
+                backtrace,
+            },
+        }
+    }
+
+    /// Removes the spans of the backtrace that are not in `source`, which
+    /// could not be resolved against its text.
+    pub(crate) fn with_spans_from(mut self, source: SourceId) -> SchemeError {
+        if let SchemeError::Traced { backtrace, .. } = &mut self {
+            for frame in backtrace {
+                frame.span = frame.span.filter(|span| span.source() == source);
+            }
+        }
+        self
+    }
+
+    /// The object a Scheme exception handler receives for this error.
+    pub(crate) fn into_condition(self) -> Value {
+        match self {
+            SchemeError::Raise(obj) => obj,
+            SchemeError::Traced { error, .. } => error.into_condition(),
+            error => Value::Error(Box::from(ErrorObject {
+                message: error.to_string_with_sources(),
+                irritants: vec![],
+                origin: Some(Box::from(error)),
+            })),
+        }
+    }
+
+    /// The message followed by those of its sources, e.g. "Cannot open
+    /// data.txt: No such file or directory".
+    pub fn to_string_with_sources(&self) -> String {
+        let mut message = self.to_string();
+        let mut source = error::Error::source(self);
+        while let Some(e) = source {
+            message.push_str(&format!(": {}", e));
+            source = e.source();
+        }
+        message
+    }
+
+    /// The error reported when `condition` is raised and no handler takes it.
+    pub(crate) fn from_condition(condition: Value) -> SchemeError {
+        match condition {
+            Value::Error(err) if err.origin.is_some() => *err.origin.unwrap(),
+            _ => SchemeError::Raise(condition),
+        }
+    }
+}
+
+impl fmt::Display for SchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemeError::ParseError(e) => write!(f, "{}", e),
+            SchemeError::UndefinedVariable { name, suggestions } => {
+                write!(f, "Undefined variable: {}", name)?;
+                if let Some((last, rest)) = suggestions.split_last() {
+                    write!(f, " (did you mean ")?;
+                    if !rest.is_empty() {
+                        write!(f, "{} or ", rest.join(", "))?;
+                    }
+                    write!(f, "{}?)", last)?;
+                }
+                Ok(())
+            }
+            SchemeError::BadProcedure(value) => write!(f, "{} is not a procedure", value),
+            SchemeError::ContinuationEscape => {
+                write!(f, "A continuation cannot escape a host function")
+            }
+            SchemeError::TypeMismatch {
+                procedure,
+                expected,
+                actual,
+                path,
+            } => {
+                if let Some(procedure) = procedure {
+                    write!(f, "{}: ", procedure)?;
+                }
+                for step in path {
+                    write!(f, "{}: ", step)?;
+                }
+                write!(f, "expected {}, got {}", expected, actual)
+            }
+            SchemeError::ArgumentMismatch {
+                procedure,
+                expected,
+                actual,
+            } => {
+                if let Some(procedure) = procedure {
+                    write!(f, "{}: ", procedure)?;
+                }
+                write!(f, "expected {}, got {}", expected, actual)
+            }
+            SchemeError::ValuesMismatch { expected, actual } => write!(
+                f,
+                "Expected {} value{}, got {}",
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                actual
+            ),
+            SchemeError::IoError { message, .. } => write!(f, "{}", message),
+            SchemeError::PermissionDenied { procedure } => {
+                write!(f, "{} is not allowed here", procedure)
+            }
+            SchemeError::ReadError(message) | SchemeError::SyntaxError(message) => {
+                write!(f, "{}", message)
+            }
+            SchemeError::Raise(Value::Error(err)) => {
+                write!(f, "{}", err.message)?;
+                for irritant in &err.irritants {
+                    write!(f, " {}", irritant)?;
+                }
+                Ok(())
+            }
+            SchemeError::Raise(value) => write!(f, "Uncaught exception: {}", value),
+            SchemeError::LimitExceeded(limit) => write!(f, "Limit exceeded: {}", limit),
+            SchemeError::Interrupted => write!(f, "Interrupted"),
+            SchemeError::EmptyProgram => write!(f, "Empty program"),
+            SchemeError::Traced { error, .. } => write!(f, "{}", error),
+        }
+    }
+}
+
+impl error::Error for SchemeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SchemeError::IoError {
+                source: Some(source),
+                ..
+            } => Some(&**source),
+            SchemeError::Traced { error, .. } => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (bound, n) = match self {
+            Arity::Exactly(n) => ("", n),
+            Arity::AtLeast(n) => ("at least ", n),
+            Arity::AtMost(n) => ("at most ", n),
+        };
+        let plural = if *n == 1 { "" } else { "s" };
+        write!(f, "{}{} argument{}", bound, n, plural)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.found {
+            Some(c) => write!(
+                f,
+                "Parse error: unexpected character '{}'",
+                c.escape_debug()
+            ),
+            None => write!(f, "Parse error: unexpected end of input"),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
@@ -1,137 +1,1488 @@
 use crate::ast::*;
+use crate::backtrace::{Span, StackFrame};
+use crate::capability::Capabilities;
+use crate::diagram::{Argument, CallFrame, EnvironmentDiagram};
 use crate::env::Environment;
-use crate::value::{Closure, Value};
-use crate::{primitives, substitution, SchemeError};
+use crate::limits::{self, CancellationToken, EvalLimits, Limit};
+use crate::port::{self, Port};
+use crate::shared::Shared;
+use crate::trace::{StepKind, TraceStep};
+use crate::value::{self, Closure, ErrorObject, Promise, PromiseState, Value};
+use crate::{parser, primitives, substitution, Arity, SchemeError};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::time::Instant;
+
+// The evaluator is a machine with an explicit continuation instead of a
+// recursive Rust function, so that `call/cc` can capture the rest of the
+// computation as a value and resume it any number of times.
+
+/// Whether operands are evaluated before a procedure is applied, or
+/// substituted into its body unevaluated and only evaluated when a primitive
+/// needs their value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvaluationOrder {
+    #[default]
+    Applicative,
+    Normal,
+}
 
 pub fn eval_program(program: &Program) -> Result<Value, SchemeError> {
-    eval_sequence(program.exps.as_slice(), &Environment::Empty)
+    eval_program_in_order(program, EvaluationOrder::Applicative)
+}
+
+pub fn normal_eval_program(program: &Program) -> Result<Value, SchemeError> {
+    eval_program_in_order(program, EvaluationOrder::Normal)
+}
+
+pub fn eval_program_in_order(
+    program: &Program,
+    order: EvaluationOrder,
+) -> Result<Value, SchemeError> {
+    let env = Environment::initial(&Capabilities::default());
+    let mut machine = Machine::new(env.clone(), order);
+    let state = machine.sequence(program.exps.clone(), env);
+    machine.run(state)
+}
+
+/// Evaluates `program`, calling `tracer` with every reduction step of the
+/// substitution model as it happens.
+pub fn eval_program_traced(
+    program: &Program,
+    order: EvaluationOrder,
+    tracer: &mut dyn FnMut(&TraceStep),
+) -> Result<Value, SchemeError> {
+    let env = Environment::initial(&Capabilities::default());
+    let mut machine = Machine::new(env.clone(), order);
+    machine.tracer = Some(tracer);
+    let state = machine.sequence(program.exps.clone(), env);
+    machine.run(state)
+}
+
+/// Evaluates `program`, calling `on_snapshot` with a diagram of the
+/// environment at every `(breakpoint)` call and at the end of evaluation.
+pub fn eval_program_with_diagrams(
+    program: &Program,
+    order: EvaluationOrder,
+    on_snapshot: &mut dyn FnMut(&EnvironmentDiagram),
+) -> Result<Value, SchemeError> {
+    let env = Environment::initial(&Capabilities::default());
+    let mut machine = Machine::new(env.clone(), order);
+    machine.on_breakpoint = Some(&mut *on_snapshot);
+    let state = machine.sequence(program.exps.clone(), env);
+    let result = machine.run(state);
+    let end = machine.env.clone();
+    on_snapshot(&EnvironmentDiagram::snapshot(
+        &end,
+        &[],
+        Some("end".to_owned()),
+    ));
+    result
+}
+
+/// Evaluates `program`, stopping with `SchemeError::LimitExceeded` as soon as
+/// it goes over one of `limits`.
+pub fn eval_program_with_limits(
+    program: &Program,
+    order: EvaluationOrder,
+    limits: EvalLimits,
+) -> Result<Value, SchemeError> {
+    let env = Environment::initial(&Capabilities::default());
+    let mut machine = Machine::new(env.clone(), order);
+    machine.limits = limits;
+    let state = machine.sequence(program.exps.clone(), env);
+    machine.run(state)
+}
+
+/// Evaluates `cexp` in `env` on a fresh machine, for an `Interpreter`.
+pub(crate) fn eval_in(
+    cexp: ConstituentExpression,
+    env: Shared<Environment>,
+    order: EvaluationOrder,
+    limits: &EvalLimits,
+    capabilities: &Capabilities,
+) -> Result<Value, SchemeError> {
+    let mut machine = Machine::new(env.clone(), order);
+    machine.limits = limits.clone();
+    machine.capabilities = capabilities.clone();
+    machine.run(Ok(State::Eval(cexp, env)))
+}
+
+/// Applies `operator` to `operands` on a fresh machine, for an `Interpreter`.
+pub(crate) fn apply_in(
+    operator: Value,
+    operands: Vec<Value>,
+    env: Shared<Environment>,
+    order: EvaluationOrder,
+    limits: &EvalLimits,
+    capabilities: &Capabilities,
+) -> Result<Value, SchemeError> {
+    let mut machine = Machine::new(env.clone(), order);
+    machine.limits = limits.clone();
+    machine.capabilities = capabilities.clone();
+    machine.run(Ok(State::Apply(operator, operands, env)))
+}
+
+/// Applies `operator` to `operands` from a host function, on a machine
+/// nested in the one that called it. It sees the caller's environment and
+/// uses what is left of its limits and its capabilities. Outside a host
+/// function, a closure runs in the environment it was created in, without
+/// limits and with only pure capabilities, since the ones of the evaluation
+/// that created it are unknown.
+pub(crate) fn apply_procedure(operator: &Value, operands: &[Value]) -> Result<Value, SchemeError> {
+    let caller = HOST_CALLS.with(|calls| {
+        calls.borrow().last().map(|call| {
+            (
+                call.env.clone(),
+                call.order,
+                call.remaining_limits(),
+                call.capabilities.clone(),
+            )
+        })
+    });
+    let (env, order, limits, capabilities) = match caller {
+        Some(caller) => caller,
+        None => {
+            let env = match operator {
+                Value::Closure(closure) => closure.env.clone(),
+                _ => Environment::initial(&Capabilities::pure()),
+            };
+            (
+                env,
+                EvaluationOrder::default(),
+                EvalLimits::default(),
+                Capabilities::pure(),
+            )
+        }
+    };
+    let mut machine = Machine::new(env.clone(), order);
+    machine.limits = limits;
+    machine.capabilities = capabilities;
+    let result = machine.run(Ok(State::Apply(operator.clone(), operands.to_vec(), env)));
+    let steps = machine.steps;
+    HOST_CALLS.with(|calls| {
+        if let Some(call) = calls.borrow_mut().last_mut() {
+            call.steps += steps;
+        }
+    });
+    result
+}
+
+// An application of a host function, in which it can apply Scheme procedures.
+struct HostCall {
+    id: u64,
+    env: Shared<Environment>,
+    order: EvaluationOrder,
+    limits: EvalLimits,
+    capabilities: Capabilities,
+    // The steps and depth of the calling machine, plus the steps of the
+    // procedures applied so far.
+    steps: u64,
+    depth: usize,
+    // The count of allocated cons cells when the calling machine started.
+    cons_cells: usize,
+}
+
+impl HostCall {
+    fn remaining_limits(&self) -> EvalLimits {
+        let limits = &self.limits;
+        let cons_cells = value::cons_cells_allocated() - self.cons_cells;
+        EvalLimits {
+            max_steps: limits.max_steps.map(|max| max.saturating_sub(self.steps)),
+            max_depth: limits.max_depth.map(|max| max.saturating_sub(self.depth)),
+            max_cons_cells: limits
+                .max_cons_cells
+                .map(|max| max.saturating_sub(cons_cells)),
+            deadline: limits.deadline,
+        }
+    }
+}
+
+thread_local! {
+    static HOST_CALLS: RefCell<Vec<HostCall>> = const { RefCell::new(Vec::new()) };
+    static NEXT_HOST_CALL: Cell<u64> = const { Cell::new(0) };
+}
+
+/// The innermost active host function application, if any.
+fn current_host_call() -> Option<u64> {
+    HOST_CALLS.with(|calls| calls.borrow().last().map(|call| call.id))
 }
 
 pub fn applicative_eval(
     cexp: &ConstituentExpression,
     env: &Environment,
 ) -> Result<Value, SchemeError> {
-    match cexp {
-        ConstituentExpression::Number(n) => Ok(Value::Number(n.0)),
-        ConstituentExpression::Boolean(b) => Ok(Value::Boolean(b.0)),
-        ConstituentExpression::Literal(sexpr) => Ok(Value::SExpression(Box::from(sexpr.clone()))),
-        ConstituentExpression::VariableReference(varref) => match env.apply(&varref.0) {
-            Ok(v) => Ok(v.clone()),
-            Err(e) => Err(e),
-        },
-        ConstituentExpression::PrimitiveOperation(primop) => {
-            Ok(Value::PrimitiveOperation(primop.clone()))
-        }
-        ConstituentExpression::If(ifexp) => eval_if(ifexp, env),
-        ConstituentExpression::Procedure(proc) => eval_procedure(proc, env),
-        ConstituentExpression::Let(letexp) => eval_let(letexp, env),
-        ConstituentExpression::Applic(applic) => eval_applic(applic, env),
-    }
+    let env = Shared::new(env.clone());
+    Machine::new(env.clone(), EvaluationOrder::Applicative).run(Ok(State::Eval(cexp.clone(), env)))
+}
+
+pub fn normal_eval(cexp: &ConstituentExpression, env: &Environment) -> Result<Value, SchemeError> {
+    let env = Shared::new(env.clone());
+    Machine::new(env.clone(), EvaluationOrder::Normal).run(Ok(State::Eval(cexp.clone(), env)))
 }
 
 pub(crate) fn is_true(v: &Value) -> bool {
     !matches!(v, Value::Boolean(false))
 }
 
-fn eval_if(ifexp: &If, env: &Environment) -> Result<Value, SchemeError> {
-    if is_true(&applicative_eval(&ifexp.cond, env)?) {
-        applicative_eval(&ifexp.then, env)
-    } else {
-        applicative_eval(&ifexp.alt, env)
+enum State {
+    Eval(ConstituentExpression, Shared<Environment>),
+    Apply(Value, Vec<Value>, Shared<Environment>),
+    Return(Value),
+}
+
+#[derive(Debug, Clone)]
+enum Frame {
+    If {
+        then: ConstituentExpression,
+        alt: ConstituentExpression,
+        env: Shared<Environment>,
+    },
+    Operator {
+        operands: Vec<ConstituentExpression>,
+        env: Shared<Environment>,
+        site: Shared<CallSite>,
+    },
+    // `pending` holds the operands still to be evaluated, last one first.
+    Operand {
+        operator: Value,
+        done: Vec<Value>,
+        pending: Vec<ConstituentExpression>,
+        env: Shared<Environment>,
+        site: Shared<CallSite>,
+    },
+    // A closure body being evaluated. A call in tail position replaces the
+    // frame and counts itself in `tail_calls`, so loops run in constant space.
+    // `frame` is only kept for environment diagrams.
+    Call {
+        site: Option<Shared<CallSite>>,
+        args: Vec<ConstituentExpression>,
+        tail_calls: usize,
+        frame: Option<Shared<CallFrame>>,
+    },
+    Sequence {
+        rest: Vec<Expression>,
+        env: Shared<Environment>,
+    },
+    Define {
+        var: String,
+        rest: Vec<Expression>,
+        env: Shared<Environment>,
+    },
+    DefineValues {
+        vars: Vec<String>,
+        rest: Vec<Expression>,
+        env: Shared<Environment>,
+    },
+    // `vars` and `vals` hold the bindings made so far; `pending` the ones still
+    // to be evaluated, last one first.
+    LetValues {
+        formals: Vec<VariableDeclaration>,
+        pending: Vec<(Vec<VariableDeclaration>, Box<ConstituentExpression>)>,
+        vars: Vec<String>,
+        vals: Vec<Value>,
+        body: Vec<ConstituentExpression>,
+        env: Shared<Environment>,
+    },
+    CallWithValues {
+        consumer: Value,
+        env: Shared<Environment>,
+    },
+    Force {
+        promise: Promise,
+        lazy: bool,
+        env: Shared<Environment>,
+    },
+    Clause {
+        clause: CondClause,
+        rest: Vec<CondClause>,
+        condition: Value,
+        env: Shared<Environment>,
+    },
+    ClauseReceiver {
+        test: Value,
+        env: Shared<Environment>,
+    },
+    RestoreHandlers(Handlers),
+    NonContinuable(Value),
+    GuardClauses {
+        var: String,
+        clauses: Vec<CondClause>,
+        env: Shared<Environment>,
+    },
+    OutputString(Port),
+    CloseOutput {
+        file: Port,
+        previous: Port,
+        outer: Winders,
+    },
+    WindEnter {
+        before: Value,
+        thunk: Value,
+        after: Value,
+    },
+    WindLeave {
+        after: Value,
+        outer: Winders,
+    },
+    Wind {
+        steps: Vec<WindStep>,
+        target: Continuation,
+        value: Value,
+    },
+    Value(Value),
+    Fail(SchemeError),
+}
+
+/// The application an operator and its operands come from, described in
+/// backtraces.
+#[derive(Debug)]
+struct CallSite {
+    name: Option<String>,
+    span: Option<Span>,
+}
+
+/// The frames waiting for the value of the current expression, innermost
+/// first. Frames are shared between captured continuations and only copied
+/// when a shared frame is resumed.
+#[derive(Debug, Clone, Default)]
+struct Cont(Option<Shared<ContNode>>);
+
+#[derive(Debug)]
+struct ContNode {
+    frame: Frame,
+    depth: usize,
+    next: Cont,
+}
+
+impl Cont {
+    fn push(&mut self, frame: Frame) {
+        let next = std::mem::take(self);
+        let depth = next.depth() + 1;
+        *self = Cont(Some(Shared::new(ContNode { frame, depth, next })));
+    }
+
+    fn depth(&self) -> usize {
+        self.0.as_ref().map_or(0, |node| node.depth)
+    }
+
+    fn top(&self) -> Option<&Frame> {
+        self.0.as_ref().map(|node| &node.frame)
+    }
+
+    fn pop(&mut self) -> Option<Frame> {
+        let node = self.0.take()?;
+        match Shared::try_unwrap(node) {
+            Ok(mut node) => {
+                *self = std::mem::take(&mut node.next);
+                Some(node.frame)
+            }
+            Err(node) => {
+                *self = node.next.clone();
+                Some(node.frame.clone())
+            }
+        }
     }
 }
 
-fn eval_procedure(proc: &Procedure, _env: &Environment) -> Result<Value, SchemeError> {
-    Ok(Value::Closure(Closure {
-        params: proc.args.to_vec(),
-        body: proc.body.to_vec(),
-    }))
+// Dropping a long chain of frames recursively could overflow the Rust stack.
+impl Drop for Cont {
+    fn drop(&mut self) {
+        let mut cur = self.0.take();
+        while let Some(node) = cur {
+            match Shared::try_unwrap(node) {
+                Ok(mut node) => cur = node.next.0.take(),
+                Err(_) => break,
+            }
+        }
+    }
 }
 
-fn eval_let(letexp: &Let, env: &Environment) -> Result<Value, SchemeError> {
-    let proc = Procedure {
-        args: letexp
-            .bindings
-            .iter()
-            .map(|(vardecl, _)| vardecl.clone())
-            .collect(),
-        body: letexp.body.to_vec(),
-    };
-    let proc = ConstituentExpression::Procedure(proc);
-    let app = Application {
-        operator: Box::from(proc),
-        operands: letexp
-            .bindings
-            .iter()
-            .map(|(_, cexp)| cexp.clone())
-            .collect(),
-    };
-    let app = ConstituentExpression::Applic(app);
-    applicative_eval(&app, env)
+/// The stack of installed exception handlers, innermost first.
+#[derive(Debug, Clone, Default)]
+struct Handlers(Option<Shared<HandlerNode>>);
+
+#[derive(Debug)]
+struct HandlerNode {
+    handler: Handler,
+    next: Handlers,
 }
 
-fn eval_applic(applic: &Application, env: &Environment) -> Result<Value, SchemeError> {
-    let operator = applicative_eval(&applic.operator, env)?;
-    let operands = applic
-        .operands
-        .iter()
-        .map(|cexp| applicative_eval(cexp, env))
-        .collect::<Result<Vec<Value>, SchemeError>>()?;
-    apply_procedure(&operator, &operands, env)
+#[derive(Debug)]
+enum Handler {
+    Procedure(Value),
+    // `k` is the continuation of the guard expression itself.
+    Guard {
+        var: String,
+        clauses: Vec<CondClause>,
+        env: Shared<Environment>,
+        k: Continuation,
+    },
 }
 
-fn apply_procedure(
-    operator: &Value,
-    operands: &[Value],
-    env: &Environment,
-) -> Result<Value, SchemeError> {
-    match operator {
-        Value::PrimitiveOperation(primop) => primitives::apply_primitive(primop, operands),
-        Value::Closure(closure @ Closure { .. }) => apply_closure(closure, operands, env),
-        _ => Err(SchemeError::BadProcedure(format!("{:?}", operator))),
+impl Handlers {
+    fn push(&self, handler: Handler) -> Handlers {
+        Handlers(Some(Shared::new(HandlerNode {
+            handler,
+            next: self.clone(),
+        })))
     }
 }
 
-fn apply_closure(proc: &Closure, args: &[Value], env: &Environment) -> Result<Value, SchemeError> {
-    let vars: Vec<_> = proc.params.iter().map(|vd| vd.0.to_owned()).collect();
-    let body = substitution::rename(&proc.body);
-    let lit_args: Vec<_> = args.iter().map(substitution::value_to_literal).collect();
-    let exps = substitution::substitute(body.as_slice(), vars.as_slice(), lit_args.as_slice())
-        .iter()
-        .map(|e| Expression::ConstituentExpression(e.clone()))
-        .collect::<Vec<_>>();
-    eval_sequence(exps.as_slice(), env)
+/// The active `dynamic-wind` extents, innermost first.
+#[derive(Debug, Clone, Default)]
+struct Winders(Option<Shared<WinderNode>>);
+
+#[derive(Debug)]
+struct WinderNode {
+    winder: Winder,
+    depth: usize,
+    next: Winders,
+}
+
+#[derive(Debug)]
+enum Winder {
+    Thunks { before: Value, after: Value },
+    Output { file: Port, previous: Port },
 }
 
-fn eval_sequence(exps: &[Expression], env: &Environment) -> Result<Value, SchemeError> {
-    match exps {
-        [] => Err(SchemeError::EmptyProgram),
-        [Expression::Define(def @ Define { .. }), rest @ ..] => eval_define_exps(def, rest, env),
-        [Expression::ConstituentExpression(cexp), es @ ..] => eval_cexps(cexp, es, env),
+#[derive(Debug, Clone)]
+struct WindStep {
+    node: Shared<WinderNode>,
+    entering: bool,
+}
+
+impl Winders {
+    fn push(&self, winder: Winder) -> Winders {
+        Winders(Some(Shared::new(WinderNode {
+            winder,
+            depth: self.depth() + 1,
+            next: self.clone(),
+        })))
+    }
+
+    fn depth(&self) -> usize {
+        self.0.as_ref().map_or(0, |node| node.depth)
+    }
+
+    fn ptr_eq(&self, other: &Winders) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Shared::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// The `after` steps leaving `self` followed by the `before` steps
+    /// entering `to`, in the order they have to run.
+    fn steps_to(&self, to: &Winders) -> Vec<WindStep> {
+        let (mut from, mut to) = (self.clone(), to.clone());
+        let mut leaving = vec![];
+        let mut entering = vec![];
+        while from.depth() > to.depth() {
+            let node = from.0.clone().unwrap();
+            from = node.next.clone();
+            leaving.push(node);
+        }
+        while to.depth() > from.depth() {
+            let node = to.0.clone().unwrap();
+            to = node.next.clone();
+            entering.push(node);
+        }
+        while !from.ptr_eq(&to) {
+            let (a, b) = (from.0.clone().unwrap(), to.0.clone().unwrap());
+            from = a.next.clone();
+            to = b.next.clone();
+            leaving.push(a);
+            entering.push(b);
+        }
+        let leaving = leaving.into_iter().map(|node| WindStep {
+            node,
+            entering: false,
+        });
+        let entering = entering.into_iter().rev().map(|node| WindStep {
+            node,
+            entering: true,
+        });
+        leaving.chain(entering).collect()
     }
 }
 
-fn eval_cexps(
-    first: &ConstituentExpression,
-    rest: &[Expression],
-    env: &Environment,
-) -> Result<Value, SchemeError> {
-    let value = applicative_eval(first, env)?;
-    if rest.is_empty() {
-        Ok(value)
-    } else {
-        eval_sequence(rest, env)
+/// A continuation captured by `call/cc`, together with the dynamic state
+/// (exception handlers and `dynamic-wind` extents) it was captured in.
+#[derive(Clone)]
+pub struct Continuation {
+    cont: Cont,
+    handlers: Handlers,
+    winders: Winders,
+    // The innermost host function application active when it was captured.
+    // Its frames are only reachable while that application runs, so the
+    // continuation can only be resumed inside it and not from an outer or a
+    // later one.
+    host_call: Option<u64>,
+}
+
+impl fmt::Debug for Continuation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#<continuation>")
     }
 }
 
-fn eval_define_exps(
-    def: &Define,
-    exps: &[Expression],
-    env: &Environment,
-) -> Result<Value, SchemeError> {
-    let val = applicative_eval(&def.val, env)?;
-    eval_sequence(
-        exps,
-        &Environment::NonEmpty(def.var.to_string(), val, Box::from(env.clone())),
+struct Machine<'a> {
+    cont: Cont,
+    handlers: Handlers,
+    winders: Winders,
+    // The environment of the expression being evaluated, in which exception
+    // handlers run.
+    env: Shared<Environment>,
+    order: EvaluationOrder,
+    tracer: Option<&'a mut dyn FnMut(&TraceStep)>,
+    on_breakpoint: Option<&'a mut dyn FnMut(&EnvironmentDiagram)>,
+    limits: EvalLimits,
+    capabilities: Capabilities,
+    cancellation: CancellationToken,
+    steps: u64,
+    // The count of allocated cons cells when evaluation started.
+    cons_cells: usize,
+    // The site of the application in `State::Apply`, if it comes from one.
+    site: Option<Shared<CallSite>>,
+    // The primitive application that failed, if any, for the backtrace.
+    failed_call: Option<StackFrame>,
+}
+
+impl<'a> Machine<'a> {
+    fn new(env: Shared<Environment>, order: EvaluationOrder) -> Machine<'a> {
+        Machine {
+            cont: Cont::default(),
+            handlers: Handlers::default(),
+            winders: Winders::default(),
+            env,
+            order,
+            tracer: None,
+            on_breakpoint: None,
+            limits: EvalLimits::default(),
+            capabilities: Capabilities::default(),
+            cancellation: limits::cancellation_token(),
+            steps: 0,
+            cons_cells: value::cons_cells_allocated(),
+            site: None,
+            failed_call: None,
+        }
+    }
+
+    fn run(&mut self, mut state: Result<State, SchemeError>) -> Result<Value, SchemeError> {
+        loop {
+            if let Some(limit) = self.exceeded_limit() {
+                return Err(self.fail(SchemeError::LimitExceeded(limit)));
+            }
+            // The environment is persistent, so stopping between two steps
+            // leaves it as it was after the last completed `define`.
+            // Stopping consumes the cancellation, so later evaluations run,
+            // but only once the outermost machine has stopped: a host function
+            // that ignores the error of a nested one must not swallow it.
+            if self.cancellation.is_cancelled() {
+                if current_host_call().is_none() {
+                    self.cancellation.reset();
+                }
+                return Err(self.fail(SchemeError::Interrupted));
+            }
+            state = match state {
+                Ok(State::Return(value)) => match self.cont.pop() {
+                    None => return Ok(value),
+                    Some(frame) => self.resume(frame, value),
+                },
+                Ok(State::Eval(cexp, env)) => self.eval(cexp, env),
+                Ok(State::Apply(operator, operands, env)) => self.apply(operator, operands, env),
+                Err(e) if e.is_catchable() && self.handlers.0.is_some() => {
+                    self.failed_call = None;
+                    self.raise(e.into_condition(), false)
+                }
+                // Leave the pending `dynamic-wind` extents before reporting
+                // the error.
+                Err(e) if e.is_catchable() && self.winders.0.is_some() => {
+                    let e = e.with_backtrace(self.backtrace());
+                    let mut cont = Cont::default();
+                    cont.push(Frame::Fail(e));
+                    let exit = Continuation {
+                        cont,
+                        handlers: Handlers::default(),
+                        winders: Winders::default(),
+                        host_call: current_host_call(),
+                    };
+                    self.jump(exit, Value::Void)
+                }
+                Err(e) => return Err(self.fail(e)),
+            };
+        }
+    }
+
+    /// Attaches the backtrace to `e` and resets the machine.
+    fn fail(&mut self, e: SchemeError) -> SchemeError {
+        let e = e.with_backtrace(self.backtrace());
+        self.abort();
+        e
+    }
+
+    /// The active procedure applications, innermost first.
+    fn backtrace(&mut self) -> Vec<StackFrame> {
+        let mut frames: Vec<_> = self.failed_call.take().into_iter().collect();
+        let mut cur = &self.cont.0;
+        while let Some(node) = cur {
+            if let Frame::Call {
+                site,
+                args,
+                tail_calls,
+                ..
+            } = &node.frame
+            {
+                frames.push(StackFrame::new(
+                    site.as_ref().and_then(|site| site.name.clone()),
+                    site.as_ref().and_then(|site| site.span),
+                    args.iter().map(|arg| arg.to_string()),
+                    *tail_calls,
+                ));
+            }
+            cur = &node.next.0;
+        }
+        frames
+    }
+
+    /// The frames of the closure applications in progress, outermost first.
+    fn call_frames(&self) -> Vec<Shared<CallFrame>> {
+        let mut frames = vec![];
+        let mut cur = &self.cont.0;
+        while let Some(node) = cur {
+            if let Frame::Call {
+                frame: Some(frame), ..
+            } = &node.frame
+            {
+                frames.push(frame.clone());
+            }
+            cur = &node.next.0;
+        }
+        frames.reverse();
+        frames
+    }
+
+    fn exceeded_limit(&mut self) -> Option<Limit> {
+        self.steps += 1;
+        let limits = &self.limits;
+        if let Some(max) = limits.max_steps.filter(|max| self.steps > *max) {
+            return Some(Limit::Steps(max));
+        }
+        if let Some(max) = limits.max_depth.filter(|max| self.cont.depth() > *max) {
+            return Some(Limit::Depth(max));
+        }
+        if let Some(max) = limits.max_cons_cells {
+            if value::cons_cells_allocated() - self.cons_cells > max {
+                return Some(Limit::ConsCells(max));
+            }
+        }
+        // Reading the clock on every step would slow evaluation down.
+        match limits.deadline {
+            Some(deadline) if self.steps.is_multiple_of(1024) && Instant::now() >= deadline => {
+                Some(Limit::Deadline)
+            }
+            _ => None,
+        }
+    }
+
+    fn eval(
+        &mut self,
+        cexp: ConstituentExpression,
+        env: Shared<Environment>,
+    ) -> Result<State, SchemeError> {
+        self.env = env.clone();
+        match cexp {
+            ConstituentExpression::Number(n) => Ok(State::Return(Value::Number(n.0))),
+            ConstituentExpression::Boolean(b) => Ok(State::Return(Value::Boolean(b.0))),
+            ConstituentExpression::Str(s) => Ok(State::Return(Value::String(s.0))),
+            ConstituentExpression::Literal(value) => Ok(State::Return(value)),
+            ConstituentExpression::VariableReference(varref) => {
+                Ok(State::Return(env.apply(&varref.0)?.clone()))
+            }
+            ConstituentExpression::PrimitiveOperation(primop) => {
+                Ok(State::Return(env.primitive(primop)?))
+            }
+            ConstituentExpression::If(ifexp) => {
+                self.cont.push(Frame::If {
+                    then: *ifexp.then,
+                    alt: *ifexp.alt,
+                    env: env.clone(),
+                });
+                Ok(State::Eval(*ifexp.cond, env))
+            }
+            ConstituentExpression::Procedure(proc) => Ok(State::Return(Value::Closure(Closure {
+                params: proc.args,
+                body: proc.body.into(),
+                env,
+            }))),
+            ConstituentExpression::Let(letexp) => Ok(State::Eval(let_to_applic(letexp), env)),
+            ConstituentExpression::LetValues(letexp) => {
+                let mut pending = letexp.bindings;
+                pending.reverse();
+                self.let_values(pending, vec![], vec![], letexp.body, env)
+            }
+            ConstituentExpression::Guard(guard) => {
+                self.cont
+                    .push(Frame::RestoreHandlers(self.handlers.clone()));
+                let k = self.capture();
+                self.handlers = self.handlers.push(Handler::Guard {
+                    var: guard.var.0,
+                    clauses: guard.clauses,
+                    env: env.clone(),
+                    k,
+                });
+                self.body(guard.body, env)
+            }
+            ConstituentExpression::Delay(delay) => Ok(State::Return(Value::Promise(Promise::new(
+                PromiseState::Delayed {
+                    expr: *delay.expr,
+                    lazy: delay.lazy,
+                },
+            )))),
+            ConstituentExpression::Applic(applic) => {
+                let name = match applic.operator.as_ref() {
+                    ConstituentExpression::VariableReference(var) => Some(var.0.clone()),
+                    ConstituentExpression::PrimitiveOperation(primop) => {
+                        Some(primop.name().to_owned())
+                    }
+                    _ => None,
+                };
+                self.cont.push(Frame::Operator {
+                    operands: applic.operands,
+                    env: env.clone(),
+                    site: Shared::new(CallSite {
+                        name,
+                        span: applic.span,
+                    }),
+                });
+                Ok(State::Eval(*applic.operator, env))
+            }
+        }
+    }
+
+    fn resume(&mut self, frame: Frame, value: Value) -> Result<State, SchemeError> {
+        match frame {
+            Frame::If { then, alt, env } => {
+                let branch = if is_true(&value) { then } else { alt };
+                self.trace(StepKind::If, || branch.to_string());
+                Ok(State::Eval(branch, env))
+            }
+            Frame::Operator {
+                operands,
+                env,
+                site,
+            } => {
+                if let (EvaluationOrder::Normal, Value::Closure(closure)) = (self.order, &value) {
+                    return self.apply_closure(closure, operands, None, env, Some(site));
+                }
+                let mut pending = operands;
+                pending.reverse();
+                match pending.pop() {
+                    None => {
+                        self.site = Some(site);
+                        Ok(State::Apply(value, vec![], env))
+                    }
+                    Some(next) => {
+                        if !is_constant(&next) {
+                            self.trace(StepKind::Operand, || next.to_string());
+                        }
+                        self.cont.push(Frame::Operand {
+                            operator: value,
+                            done: vec![],
+                            pending,
+                            env: env.clone(),
+                            site,
+                        });
+                        Ok(State::Eval(next, env))
+                    }
+                }
+            }
+            Frame::Operand {
+                operator,
+                mut done,
+                mut pending,
+                env,
+                site,
+            } => {
+                done.push(value);
+                match pending.pop() {
+                    None => {
+                        self.site = Some(site);
+                        Ok(State::Apply(operator, done, env))
+                    }
+                    Some(next) => {
+                        if !is_constant(&next) {
+                            self.trace(StepKind::Operand, || next.to_string());
+                        }
+                        self.cont.push(Frame::Operand {
+                            operator,
+                            done,
+                            pending,
+                            env: env.clone(),
+                            site,
+                        });
+                        Ok(State::Eval(next, env))
+                    }
+                }
+            }
+            Frame::Sequence { rest, env } => self.sequence(rest, env),
+            Frame::Define { var, rest, env } => {
+                self.sequence(rest, Shared::new(Environment::NonEmpty(var, value, env)))
+            }
+            Frame::DefineValues { vars, rest, env } => {
+                let values = value.into_values();
+                if values.len() != vars.len() {
+                    return Err(values_error(vars.len(), values.len()));
+                }
+                let env = vars.into_iter().zip(values).fold(env, |env, (var, value)| {
+                    Shared::new(Environment::NonEmpty(var, value, env))
+                });
+                self.sequence(rest, env)
+            }
+            Frame::LetValues {
+                formals,
+                pending,
+                mut vars,
+                mut vals,
+                body,
+                env,
+            } => {
+                let values = value.into_values();
+                if values.len() != formals.len() {
+                    return Err(values_error(formals.len(), values.len()));
+                }
+                vars.extend(formals.into_iter().map(|vd| vd.0));
+                vals.extend(values);
+                self.let_values(pending, vars, vals, body, env)
+            }
+            Frame::CallWithValues { consumer, env } => {
+                Ok(State::Apply(consumer, value.into_values(), env))
+            }
+            // The promise may have been forced while its expression was being
+            // evaluated, in which case that first value wins.
+            Frame::Force { promise, lazy, env } => {
+                if let PromiseState::Delayed { .. } = promise.state() {
+                    match value {
+                        Value::Promise(next) if lazy => promise.update_from(&next),
+                        value => promise.fulfill(value),
+                    }
+                }
+                self.force(promise, env)
+            }
+            Frame::Clause {
+                clause,
+                rest,
+                condition,
+                env,
+            } => {
+                if !is_true(&value) {
+                    return self.clauses(rest, condition, env);
+                }
+                let mut body = clause.body;
+                match body.pop() {
+                    None => Ok(State::Return(value)),
+                    Some(receiver) if clause.arrow => {
+                        self.cont.push(Frame::ClauseReceiver {
+                            test: value,
+                            env: env.clone(),
+                        });
+                        Ok(State::Eval(receiver, env))
+                    }
+                    Some(last) => {
+                        body.push(last);
+                        self.body(body, env)
+                    }
+                }
+            }
+            Frame::ClauseReceiver { test, env } => Ok(State::Apply(value, vec![test], env)),
+            Frame::RestoreHandlers(handlers) => {
+                self.handlers = handlers;
+                Ok(State::Return(value))
+            }
+            Frame::NonContinuable(obj) => {
+                let secondary = ErrorObject {
+                    message: "Exception handler returned from non-continuable raise".to_owned(),
+                    irritants: vec![obj],
+                    origin: None,
+                };
+                self.raise(Value::Error(Box::from(secondary)), false)
+            }
+            Frame::GuardClauses { var, clauses, env } => {
+                let clauses = substitution::substitute_clauses(
+                    &clauses,
+                    std::slice::from_ref(&var),
+                    &[substitution::value_to_literal(&value)],
+                );
+                self.clauses(clauses, value, env)
+            }
+            Frame::OutputString(port) => Ok(State::Return(Value::String(
+                port.output_string_contents().unwrap_or_default(),
+            ))),
+            Frame::CloseOutput {
+                file,
+                previous,
+                outer,
+            } => {
+                self.winders = outer;
+                port::set_current_output_port(previous);
+                file.close()?;
+                Ok(State::Return(value))
+            }
+            Frame::WindEnter {
+                before,
+                thunk,
+                after,
+            } => {
+                let outer = self.winders.clone();
+                self.winders = outer.push(Winder::Thunks {
+                    before,
+                    after: after.clone(),
+                });
+                self.cont.push(Frame::WindLeave { after, outer });
+                Ok(State::Apply(thunk, vec![], self.env.clone()))
+            }
+            Frame::WindLeave { after, outer } => {
+                self.winders = outer;
+                self.cont.push(Frame::Value(value));
+                Ok(State::Apply(after, vec![], self.env.clone()))
+            }
+            Frame::Wind {
+                steps,
+                target,
+                value,
+            } => self.wind(steps, target, value),
+            Frame::Value(value) => Ok(State::Return(value)),
+            Frame::Call { .. } => Ok(State::Return(value)),
+            Frame::Fail(e) => Err(e),
+        }
+    }
+
+    fn apply(
+        &mut self,
+        operator: Value,
+        operands: Vec<Value>,
+        env: Shared<Environment>,
+    ) -> Result<State, SchemeError> {
+        self.env = env.clone();
+        let site = self.site.take();
+        match operator {
+            Value::PrimitiveOperation(primop) => {
+                let result = self
+                    .capabilities
+                    .check(primop, &operands)
+                    .and_then(|_| self.apply_primitive(primop, &operands, env))
+                    .map_err(|e| e.in_procedure(primop.name()));
+                if result.is_err() {
+                    let args = operands.iter().map(|arg| arg.to_string());
+                    let span = site.and_then(|site| site.span);
+                    self.failed_call = Some(StackFrame::new(
+                        Some(primop.name().to_owned()),
+                        span,
+                        args,
+                        0,
+                    ));
+                }
+                result
+            }
+            Value::HostFunction(func) => {
+                let id = NEXT_HOST_CALL.with(|next| next.replace(next.get() + 1));
+                HOST_CALLS.with(|calls| {
+                    calls.borrow_mut().push(HostCall {
+                        id,
+                        env: env.clone(),
+                        order: self.order,
+                        limits: self.limits.clone(),
+                        capabilities: self.capabilities.clone(),
+                        steps: self.steps,
+                        depth: self.cont.depth(),
+                        cons_cells: self.cons_cells,
+                    })
+                });
+                let result = func.call(&operands);
+                if let Some(call) = HOST_CALLS.with(|calls| calls.borrow_mut().pop()) {
+                    self.steps = call.steps;
+                }
+                if result.is_err() {
+                    let args = operands.iter().map(|arg| arg.to_string());
+                    let span = site.and_then(|site| site.span);
+                    let name = Some(func.name().to_owned());
+                    self.failed_call = Some(StackFrame::new(name, span, args, 0));
+                }
+                Ok(State::Return(result?))
+            }
+            Value::Closure(closure) => {
+                let lit_args: Vec<_> = operands
+                    .iter()
+                    .map(substitution::value_to_literal)
+                    .collect();
+                self.apply_closure(&closure, lit_args, Some(operands), env, site)
+            }
+            Value::Continuation(k) if k.host_call != current_host_call() => {
+                Err(SchemeError::ContinuationEscape)
+            }
+            Value::Continuation(k) => self.jump(k, Value::values(operands)),
+            _ => Err(SchemeError::BadProcedure(operator)),
+        }
+    }
+
+    fn apply_primitive(
+        &mut self,
+        primop: PrimitiveOperation,
+        operands: &[Value],
+        env: Shared<Environment>,
+    ) -> Result<State, SchemeError> {
+        match (primop, operands) {
+            (PrimitiveOperation::CallCC, [proc])
+            | (PrimitiveOperation::CallWithCurrentContinuation, [proc]) => {
+                let k = self.capture();
+                Ok(State::Apply(
+                    proc.clone(),
+                    vec![Value::Continuation(k)],
+                    env,
+                ))
+            }
+            (PrimitiveOperation::Eval, [datum, Value::Environment(eval_env)]) => {
+                Ok(State::Eval(parser::datum_to_cexp(datum)?, eval_env.clone()))
+            }
+            (PrimitiveOperation::Eval, [_, v]) => {
+                Err(SchemeError::type_mismatch("an environment", v))
+            }
+            (PrimitiveOperation::InteractionEnvironment, []) => {
+                Ok(State::Return(Value::Environment(env)))
+            }
+            (
+                PrimitiveOperation::SchemeReportEnvironment | PrimitiveOperation::NullEnvironment,
+                _,
+            ) => Ok(State::Return(primitives::standard_environment(
+                operands,
+                env.without_bindings(),
+            )?)),
+            (PrimitiveOperation::OpenInputFile, [Value::String(path)]) => {
+                let file = self.capabilities.input_file(path)?;
+                Ok(State::Return(primitives::open_file(path, &file)?))
+            }
+            (PrimitiveOperation::CallWithOutputString, [proc]) => {
+                let port = Port::output_string();
+                self.cont.push(Frame::OutputString(port.clone()));
+                Ok(State::Apply(proc.clone(), vec![Value::Port(port)], env))
+            }
+            (PrimitiveOperation::WithOutputToFile, [path, thunk]) => {
+                let file = match primitives::open_output_file(std::slice::from_ref(path))? {
+                    Value::Port(port) => port,
+                    _ => unreachable!("open_output_file returns a port"),
+                };
+                let previous = port::set_current_output_port(file.clone());
+                let outer = self.winders.clone();
+                self.winders = outer.push(Winder::Output {
+                    file: file.clone(),
+                    previous: previous.clone(),
+                });
+                self.cont.push(Frame::CloseOutput {
+                    file,
+                    previous,
+                    outer,
+                });
+                Ok(State::Apply(thunk.clone(), vec![], env))
+            }
+            (PrimitiveOperation::Error, [message, irritants @ ..]) => {
+                let err = ErrorObject {
+                    message: message.display().to_string(),
+                    irritants: irritants.to_vec(),
+                    origin: None,
+                };
+                self.raise(Value::Error(Box::from(err)), false)
+            }
+            (PrimitiveOperation::DynamicWind, [before, thunk, after]) => {
+                self.cont.push(Frame::WindEnter {
+                    before: before.clone(),
+                    thunk: thunk.clone(),
+                    after: after.clone(),
+                });
+                Ok(State::Apply(before.clone(), vec![], env))
+            }
+            (PrimitiveOperation::CallWithValues, [producer, consumer]) => {
+                self.cont.push(Frame::CallWithValues {
+                    consumer: consumer.clone(),
+                    env: env.clone(),
+                });
+                Ok(State::Apply(producer.clone(), vec![], env))
+            }
+            (PrimitiveOperation::Force, [Value::Promise(promise)]) => {
+                self.force(promise.clone(), env)
+            }
+            (PrimitiveOperation::Force, [value]) => Ok(State::Return(value.clone())),
+            (PrimitiveOperation::StreamCdr, _) => {
+                match primitives::apply_primitive(&PrimitiveOperation::Cdr, operands)? {
+                    Value::Promise(promise) => self.force(promise, env),
+                    value => Ok(State::Return(value)),
+                }
+            }
+            (PrimitiveOperation::Breakpoint, [] | [_]) => {
+                if self.on_breakpoint.is_some() {
+                    let calls = self.call_frames();
+                    let label = operands.first().map(|label| label.display().to_string());
+                    if let Some(on_breakpoint) = &mut self.on_breakpoint {
+                        on_breakpoint(&EnvironmentDiagram::snapshot(&env, &calls, label));
+                    }
+                }
+                Ok(State::Return(Value::Void))
+            }
+            (PrimitiveOperation::Breakpoint, _) => {
+                Err(SchemeError::arity(Arity::AtMost(1), operands.len()))
+            }
+            (PrimitiveOperation::Raise, [obj]) => self.raise(obj.clone(), false),
+            (PrimitiveOperation::RaiseContinuable, [obj]) => self.raise(obj.clone(), true),
+            (PrimitiveOperation::WithExceptionHandler, [handler, thunk]) => {
+                self.cont
+                    .push(Frame::RestoreHandlers(self.handlers.clone()));
+                self.handlers = self.handlers.push(Handler::Procedure(handler.clone()));
+                Ok(State::Apply(thunk.clone(), vec![], env))
+            }
+            (
+                PrimitiveOperation::CallCC
+                | PrimitiveOperation::CallWithCurrentContinuation
+                | PrimitiveOperation::CallWithOutputString
+                | PrimitiveOperation::Force
+                | PrimitiveOperation::Raise
+                | PrimitiveOperation::RaiseContinuable,
+                _,
+            ) => Err(arity_error(1, operands.len())),
+            (
+                PrimitiveOperation::Eval
+                | PrimitiveOperation::WithOutputToFile
+                | PrimitiveOperation::WithExceptionHandler
+                | PrimitiveOperation::CallWithValues,
+                _,
+            ) => Err(arity_error(2, operands.len())),
+            (PrimitiveOperation::InteractionEnvironment, _) => Err(arity_error(0, operands.len())),
+            (PrimitiveOperation::DynamicWind, _) => Err(arity_error(3, operands.len())),
+            (PrimitiveOperation::Error, _) => Err(SchemeError::arity(Arity::AtLeast(1), 0)),
+            _ => Ok(State::Return(primitives::apply_primitive(
+                &primop, operands,
+            )?)),
+        }
+    }
+
+    // In normal order `args` are the operand expressions themselves, and
+    // there are no `values`.
+    fn apply_closure(
+        &mut self,
+        closure: &Closure,
+        args: Vec<ConstituentExpression>,
+        values: Option<Vec<Value>>,
+        env: Shared<Environment>,
+        site: Option<Shared<CallSite>>,
+    ) -> Result<State, SchemeError> {
+        if closure.params.len() != args.len() {
+            return Err(arity_error(closure.params.len(), args.len()));
+        }
+        let vars: Vec<_> = closure.params.iter().map(|vd| vd.0.to_owned()).collect();
+        let frame = self.on_breakpoint.is_some().then(|| {
+            let arguments: Vec<_> = match values {
+                Some(values) => values.into_iter().map(Argument::Value).collect(),
+                None => args.iter().cloned().map(Argument::Operand).collect(),
+            };
+            Shared::new(CallFrame {
+                bindings: vars.iter().cloned().zip(arguments).collect(),
+                parent: closure.env.clone(),
+            })
+        });
+        let body = substitution::rename(&closure.body);
+        if self.tracer.is_some() && unparse(&body) != unparse(&closure.body) {
+            self.trace(StepKind::Rename, || unparse(&body));
+        }
+        let body = substitution::substitute(&body, &vars, &args);
+        self.trace(StepKind::Substitute, || unparse(&body));
+        let tail_calls = match self.cont.top() {
+            Some(Frame::Call { tail_calls, .. }) => {
+                let tail_calls = tail_calls + 1;
+                self.cont.pop();
+                tail_calls
+            }
+            _ => 0,
+        };
+        self.cont.push(Frame::Call {
+            site,
+            args,
+            tail_calls,
+            frame,
+        });
+        self.body(body, env)
+    }
+
+    fn trace(&mut self, kind: StepKind, expression: impl FnOnce() -> String) {
+        if let Some(tracer) = &mut self.tracer {
+            tracer(&TraceStep {
+                kind,
+                depth: self.cont.depth(),
+                expression: expression(),
+            });
+        }
+    }
+
+    fn sequence(
+        &mut self,
+        exps: Vec<Expression>,
+        env: Shared<Environment>,
+    ) -> Result<State, SchemeError> {
+        let mut exps = exps.into_iter();
+        let first = exps.next().ok_or(SchemeError::EmptyProgram)?;
+        let rest: Vec<_> = exps.collect();
+        match first {
+            Expression::Define(def) => {
+                self.cont.push(Frame::Define {
+                    var: def.var,
+                    rest,
+                    env: env.clone(),
+                });
+                Ok(State::Eval(def.val, env))
+            }
+            Expression::DefineValues(def) => {
+                self.cont.push(Frame::DefineValues {
+                    vars: def.vars,
+                    rest,
+                    env: env.clone(),
+                });
+                Ok(State::Eval(def.val, env))
+            }
+            Expression::ConstituentExpression(cexp) => {
+                if !rest.is_empty() {
+                    self.cont.push(Frame::Sequence {
+                        rest,
+                        env: env.clone(),
+                    });
+                }
+                Ok(State::Eval(cexp, env))
+            }
+        }
+    }
+
+    fn body(
+        &mut self,
+        body: Vec<ConstituentExpression>,
+        env: Shared<Environment>,
+    ) -> Result<State, SchemeError> {
+        let exps = body
+            .into_iter()
+            .map(Expression::ConstituentExpression)
+            .collect();
+        self.sequence(exps, env)
+    }
+
+    // A `delay-force` chain is forced in a loop through `Frame::Force`, so the
+    // continuation does not grow with its length. Like a closure body, the
+    // expression is evaluated where it is forced: substitution has already
+    // replaced its local variables, and the globals it refers to may have been
+    // defined after the `delay`.
+    fn force(&mut self, promise: Promise, env: Shared<Environment>) -> Result<State, SchemeError> {
+        match promise.state() {
+            PromiseState::Done(value) => Ok(State::Return(value)),
+            PromiseState::Delayed { expr, lazy } => {
+                self.cont.push(Frame::Force {
+                    promise,
+                    lazy,
+                    env: env.clone(),
+                });
+                Ok(State::Eval(expr, env))
+            }
+        }
+    }
+
+    fn let_values(
+        &mut self,
+        mut pending: Vec<(Vec<VariableDeclaration>, Box<ConstituentExpression>)>,
+        vars: Vec<String>,
+        vals: Vec<Value>,
+        body: Vec<ConstituentExpression>,
+        env: Shared<Environment>,
+    ) -> Result<State, SchemeError> {
+        match pending.pop() {
+            None => {
+                let body = substitution::rename(&body);
+                let lit_vals: Vec<_> = vals.iter().map(substitution::value_to_literal).collect();
+                self.body(substitution::substitute(&body, &vars, &lit_vals), env)
+            }
+            Some((formals, cexp)) => {
+                self.cont.push(Frame::LetValues {
+                    formals,
+                    pending,
+                    vars,
+                    vals,
+                    body,
+                    env: env.clone(),
+                });
+                Ok(State::Eval(*cexp, env))
+            }
+        }
+    }
+
+    fn clauses(
+        &mut self,
+        clauses: Vec<CondClause>,
+        condition: Value,
+        env: Shared<Environment>,
+    ) -> Result<State, SchemeError> {
+        let mut clauses = clauses.into_iter();
+        let clause = match clauses.next() {
+            None => return self.raise(condition, true),
+            Some(clause) => clause,
+        };
+        match clause.test.clone() {
+            None => self.body(clause.body, env),
+            Some(test) => {
+                self.cont.push(Frame::Clause {
+                    clause,
+                    rest: clauses.collect(),
+                    condition,
+                    env: env.clone(),
+                });
+                Ok(State::Eval(*test, env))
+            }
+        }
+    }
+
+    // A procedure handler runs in the dynamic context of the `raise`, with
+    // the outer handlers installed. A guard first jumps back to its own
+    // context, leaving any `dynamic-wind` extents in between.
+    fn raise(&mut self, obj: Value, continuable: bool) -> Result<State, SchemeError> {
+        let node = match &self.handlers.0 {
+            None => return Err(SchemeError::from_condition(obj)),
+            Some(node) => node.clone(),
+        };
+        match &node.handler {
+            Handler::Procedure(handler) => {
+                self.cont
+                    .push(Frame::RestoreHandlers(self.handlers.clone()));
+                if !continuable {
+                    self.cont.push(Frame::NonContinuable(obj.clone()));
+                }
+                self.handlers = node.next.clone();
+                Ok(State::Apply(handler.clone(), vec![obj], self.env.clone()))
+            }
+            Handler::Guard {
+                var,
+                clauses,
+                env,
+                k,
+            } => {
+                let mut target = k.clone();
+                target.cont.push(Frame::GuardClauses {
+                    var: var.clone(),
+                    clauses: clauses.clone(),
+                    env: env.clone(),
+                });
+                self.jump(target, obj)
+            }
+        }
+    }
+
+    fn capture(&self) -> Continuation {
+        Continuation {
+            cont: self.cont.clone(),
+            handlers: self.handlers.clone(),
+            winders: self.winders.clone(),
+            host_call: current_host_call(),
+        }
+    }
+
+    /// Transfers control to `target`, running the `after` and `before`
+    /// thunks of the `dynamic-wind` extents that are left and entered.
+    fn jump(&mut self, target: Continuation, value: Value) -> Result<State, SchemeError> {
+        let mut steps = self.winders.steps_to(&target.winders);
+        steps.reverse();
+        self.wind(steps, target, value)
+    }
+
+    // `steps` is in reverse order, so the next step is popped off the end.
+    fn wind(
+        &mut self,
+        mut steps: Vec<WindStep>,
+        target: Continuation,
+        value: Value,
+    ) -> Result<State, SchemeError> {
+        while let Some(step) = steps.pop() {
+            // Both thunks run outside of their own extent.
+            self.winders = step.node.next.clone();
+            match &step.node.winder {
+                Winder::Thunks { before, after } => {
+                    let thunk = if step.entering { before } else { after };
+                    self.cont.push(Frame::Wind {
+                        steps,
+                        target,
+                        value,
+                    });
+                    return Ok(State::Apply(thunk.clone(), vec![], self.env.clone()));
+                }
+                Winder::Output { file, previous } => {
+                    let port = if step.entering { file } else { previous };
+                    port::set_current_output_port(port.clone());
+                }
+            }
+        }
+        self.cont = target.cont;
+        self.handlers = target.handlers;
+        self.winders = target.winders;
+        Ok(State::Return(value))
+    }
+
+    /// Abandons the computation without running any Scheme code, restoring
+    /// only the state the interpreter itself changed.
+    fn abort(&mut self) {
+        for step in self.winders.steps_to(&Winders::default()) {
+            if let Winder::Output { previous, .. } = &step.node.winder {
+                port::set_current_output_port(previous.clone());
+            }
+        }
+        self.cont = Cont::default();
+        self.handlers = Handlers::default();
+        self.winders = Winders::default();
+    }
+}
+
+fn let_to_applic(letexp: Let) -> ConstituentExpression {
+    let (args, operands) = letexp
+        .bindings
+        .into_iter()
+        .map(|(vardecl, cexp)| (vardecl, *cexp))
+        .unzip();
+    let proc = ConstituentExpression::Procedure(Procedure {
+        args,
+        body: letexp.body,
+    });
+    ConstituentExpression::Applic(Application {
+        operator: Box::from(proc),
+        operands,
+        span: None,
+    })
+}
+
+fn arity_error(expected: usize, got: usize) -> SchemeError {
+    SchemeError::arity(Arity::Exactly(expected), got)
+}
+
+pub(crate) fn values_error(expected: usize, got: usize) -> SchemeError {
+    SchemeError::ValuesMismatch {
+        expected,
+        actual: got,
+    }
+}
+
+fn is_constant(cexp: &ConstituentExpression) -> bool {
+    matches!(
+        cexp,
+        ConstituentExpression::Number(_)
+            | ConstituentExpression::Boolean(_)
+            | ConstituentExpression::Str(_)
+            | ConstituentExpression::Literal(_)
+            | ConstituentExpression::PrimitiveOperation(_)
     )
 }
+
+fn unparse(body: &[ConstituentExpression]) -> String {
+    body.iter()
+        .map(|cexp| cexp.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,271 @@
+use crate::ast::{ConstituentExpression, Expression};
+use crate::backtrace::SourceId;
+use crate::capability::Capabilities;
+use crate::convert::{FromArgs, IntoValue};
+use crate::env::Environment;
+use crate::eval::{self, EvaluationOrder};
+use crate::limits::EvalLimits;
+use crate::parser;
+use crate::port::{self, Port};
+use crate::shared::{SendSync, Shared};
+use crate::value::{Closure, HostFunction, Value};
+use crate::{ParseError, SchemeError};
+use nom::error::ErrorKind;
+use std::fs;
+use std::path::Path;
+
+/// An interpreter that keeps its global environment between evaluations, so a
+/// Rust application can load definitions once and call into them later.
+///
+/// Each top-level expression is evaluated on its own, the way a REPL does:
+/// a `define` is visible to every later evaluation, and a continuation
+/// captured by one expression does not include the ones after it.
+#[derive(Clone)]
+pub struct Interpreter {
+    env: Shared<Environment>,
+    order: EvaluationOrder,
+    limits: EvalLimits,
+    capabilities: Capabilities,
+    // Ports installed as the current ones while the interpreter runs; `None`
+    // keeps the thread's.
+    input: Option<Port>,
+    output: Option<Port>,
+    error: Option<Port>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter {
+            env: Environment::initial(&Capabilities::default()),
+            order: EvaluationOrder::Applicative,
+            limits: EvalLimits::default(),
+            capabilities: Capabilities::default(),
+            input: None,
+            output: None,
+            error: None,
+        }
+    }
+
+    /// An interpreter whose programs can only use `capabilities`, such as
+    /// `Capabilities::pure()` for untrusted code. Host functions are not
+    /// restricted.
+    pub fn with_capabilities(capabilities: Capabilities) -> Interpreter {
+        Interpreter {
+            env: Environment::initial(&capabilities),
+            capabilities,
+            ..Interpreter::new()
+        }
+    }
+
+    pub fn set_order(&mut self, order: EvaluationOrder) {
+        self.order = order;
+    }
+
+    /// Bounds every later evaluation; each top-level expression and each
+    /// `call` gets its own step budget.
+    pub fn set_limits(&mut self, limits: EvalLimits) {
+        self.limits = limits;
+    }
+
+    pub fn set_input_port(&mut self, port: Port) {
+        self.input = Some(port);
+    }
+
+    pub fn set_output_port(&mut self, port: Port) {
+        self.output = Some(port);
+    }
+
+    pub fn set_error_port(&mut self, port: Port) {
+        self.error = Some(port);
+    }
+
+    /// Evaluates a program, either wrapped in `(L3 ...)` or as bare top-level
+    /// expressions, and returns the value of the last expression.
+    ///
+    /// The spans in the backtrace of an error are those of `src`; procedures
+    /// defined by earlier evaluations were parsed from another text, so their
+    /// frames have none.
+    pub fn eval_str(&mut self, src: &str) -> Result<Value, SchemeError> {
+        let (source, exps) = parse(src)?;
+        self.with_ports(|interp| {
+            let mut result = Value::Void;
+            for exp in exps {
+                result = interp
+                    .eval_expression(exp)
+                    .map_err(|e| e.with_spans_from(source))?;
+            }
+            Ok(interp.with_globals(result))
+        })
+    }
+
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<Value, SchemeError> {
+        let path = path.as_ref();
+        let src = fs::read_to_string(path)
+            .map_err(|e| SchemeError::io(format!("Cannot read {}", path.display()), e))?;
+        self.eval_str(&src)
+    }
+
+    /// Binds `name` in the global environment, replacing any previous binding.
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.env = Environment::rebind(&self.env, name, value);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.env
+            .lookup(name)
+            .map(|value| self.with_globals(value.clone()))
+    }
+
+    /// Defines `name` as a procedure of `arity` arguments implemented by `func`.
+    pub fn register_fn(
+        &mut self,
+        name: &str,
+        arity: usize,
+        func: impl Fn(&[Value]) -> Result<Value, SchemeError> + SendSync + 'static,
+    ) {
+        self.define(
+            name,
+            Value::HostFunction(HostFunction::new(name, arity, func)),
+        );
+    }
+
+    /// Defines `name` as a procedure whose arguments are converted to the
+    /// tuple `A` and whose result is converted from `R`, raising an `Err`, e.g.
+    /// `register_typed::<(f64, String), bool>("f", |(n, s)| ...)`.
+    pub fn register_typed<A, R>(&mut self, name: &str, func: impl Fn(A) -> R + SendSync + 'static)
+    where
+        A: FromArgs,
+        R: IntoValue,
+    {
+        self.register_fn(name, A::ARITY, move |args| {
+            func(A::from_args(args)?).into_result()
+        });
+    }
+
+    /// Applies the procedure bound to `name` to `args`.
+    pub fn call(&mut self, name: &str, args: &[Value]) -> Result<Value, SchemeError> {
+        let procedure = match self.env.lookup(name) {
+            Some(procedure) => procedure.clone(),
+            None => return Err(self.env.undefined(name)),
+        };
+        self.with_ports(|interp| {
+            eval::apply_in(
+                procedure,
+                args.to_vec(),
+                interp.env.clone(),
+                interp.order,
+                &interp.limits,
+                &interp.capabilities,
+            )
+        })
+    }
+
+    /// Applies a Scheme procedure to `args`. A host function can use it to
+    /// call the procedures it is passed, such as a comparator; the procedure
+    /// runs in the environment, evaluation order and remaining limits of the
+    /// evaluation that called the host function, and its errors carry the
+    /// backtrace through both. Outside a host function, a closure runs in the
+    /// environment it was created in, without limits and with only
+    /// `Capabilities::pure()`, so it cannot use ports; use `call` to apply it
+    /// with an interpreter's settings instead. Closures returned by
+    /// `get_global` and `eval_str` carry the global environment as it was
+    /// then, so they see their own definition and the ones before it.
+    pub fn apply(procedure: &Value, args: &[Value]) -> Result<Value, SchemeError> {
+        eval::apply_procedure(procedure, args)
+    }
+
+    fn eval_expression(&mut self, exp: Expression) -> Result<Value, SchemeError> {
+        match exp {
+            Expression::Define(def) => {
+                let value = self.eval_cexp(def.val)?;
+                self.define(&def.var, value);
+                Ok(Value::Void)
+            }
+            Expression::DefineValues(def) => {
+                let values = self.eval_cexp(def.val)?.into_values();
+                if values.len() != def.vars.len() {
+                    return Err(eval::values_error(def.vars.len(), values.len()));
+                }
+                for (var, value) in def.vars.iter().zip(values) {
+                    self.define(var, value);
+                }
+                Ok(Value::Void)
+            }
+            Expression::ConstituentExpression(cexp) => self.eval_cexp(cexp),
+        }
+    }
+
+    fn eval_cexp(&mut self, cexp: ConstituentExpression) -> Result<Value, SchemeError> {
+        eval::eval_in(
+            cexp,
+            self.env.clone(),
+            self.order,
+            &self.limits,
+            &self.capabilities,
+        )
+    }
+
+    // A closure made in the global environment only holds the definitions
+    // before its own; the current ones are handed out with it so that it can
+    // be applied outside a host function. Storing that in the environment
+    // would make a cycle.
+    fn with_globals(&self, value: Value) -> Value {
+        match value {
+            Value::Closure(closure) if closure.env.root() == self.env.root() => {
+                Value::Closure(Closure {
+                    env: self.env.clone(),
+                    ..closure
+                })
+            }
+            value => value,
+        }
+    }
+
+    // Installs the configured ports for the duration of `f`.
+    fn with_ports<T>(&mut self, f: impl FnOnce(&mut Interpreter) -> T) -> T {
+        let input = self.input.clone().map(port::set_current_input_port);
+        let output = self.output.clone().map(port::set_current_output_port);
+        let error = self.error.clone().map(port::set_current_error_port);
+        let result = f(self);
+        if let Some(input) = input {
+            port::set_current_input_port(input);
+        }
+        if let Some(output) = output {
+            port::set_current_output_port(output);
+        }
+        if let Some(error) = error {
+            port::set_current_error_port(error);
+        }
+        result
+    }
+}
+
+fn parse(src: &str) -> Result<(SourceId, Vec<Expression>), SchemeError> {
+    parser::in_source(src, |source| {
+        if let Ok((rest, program)) = parser::parse_program(src) {
+            if rest.is_empty() {
+                return Ok((source, program.exps));
+            }
+        }
+        let error = |e| SchemeError::ParseError(ParseError::new(src, e));
+        match parser::parse_expressions(src) {
+            Ok(("", exps)) => Ok((source, exps)),
+            // Stopped before an expression it could not parse.
+            Ok((rest, _)) => match parser::parse_expressions(rest) {
+                Err(e) => Err(error(e)),
+                Ok(_) => Err(SchemeError::ParseError(ParseError::at(
+                    ErrorKind::Eof,
+                    src,
+                    rest,
+                ))),
+            },
+            Err(e) => Err(error(e)),
+        }
+    })
+}
@@ -0,0 +1,76 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Bounds on the resources a program may use. Exceeding one stops evaluation
+/// with `SchemeError::LimitExceeded`, which Scheme handlers cannot catch.
+#[derive(Debug, Clone, Default)]
+pub struct EvalLimits {
+    // Evaluator steps, counting every evaluation, application and return.
+    pub max_steps: Option<u64>,
+    // Frames of pending computation, which grows with non-tail calls.
+    pub max_depth: Option<usize>,
+    pub max_cons_cells: Option<usize>,
+    pub deadline: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Steps(u64),
+    Depth(usize),
+    ConsCells(usize),
+    Deadline,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Steps(max) => write!(f, "more than {} evaluation steps", max),
+            Limit::Depth(max) => write!(f, "call depth over {}", max),
+            Limit::ConsCells(max) => write!(f, "more than {} cons cells allocated", max),
+            Limit::Deadline => write!(f, "deadline passed"),
+        }
+    }
+}
+
+/// A flag another thread can set to stop the evaluation running on the
+/// thread it is installed on. The evaluation it stops clears the flag again;
+/// a cancellation while nothing runs stops the next evaluation, unless it is
+/// `reset` first. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Withdraws a cancellation that has not stopped an evaluation yet.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static CANCELLATION_TOKEN: RefCell<CancellationToken> = RefCell::new(CancellationToken::new());
+}
+
+/// The token the evaluations started on this thread poll.
+pub fn cancellation_token() -> CancellationToken {
+    CANCELLATION_TOKEN.with(|token| token.borrow().clone())
+}
+
+/// Replaces the cancellation token of this thread, returning the previous one.
+pub fn set_cancellation_token(token: CancellationToken) -> CancellationToken {
+    CANCELLATION_TOKEN.with(|current| current.replace(token))
+}
@@ -1,20 +1,32 @@
 mod ast;
+pub mod backtrace;
+pub mod capability;
+pub mod convert;
+pub mod diagram;
 mod env;
+mod error;
 mod eval;
+mod interpreter;
+pub mod limits;
 mod parser;
+pub mod port;
 mod primitives;
+#[cfg(feature = "serde")]
+pub mod sexp;
+pub mod shared;
 mod substitution;
+mod suggest;
+pub mod trace;
 pub mod value;
 
-pub use eval::{applicative_eval, eval_program};
-pub use parser::{parse_cexp, parse_program};
-
-#[derive(Debug)]
-pub enum SchemeError {
-    ParseError,
-    UndefinedVariable(String),
-    BadProcedure(String),
-    TypeMismatch(String),
-    ArgumentMismatch(String),
-    EmptyProgram,
-}
+pub use error::{Arity, ParseError, SchemeError};
+pub use eval::{
+    applicative_eval, eval_program, eval_program_in_order, eval_program_traced,
+    eval_program_with_diagrams, eval_program_with_limits, normal_eval, normal_eval_program,
+    EvaluationOrder,
+};
+pub use interpreter::Interpreter;
+pub use limits::{CancellationToken, EvalLimits};
+pub use parser::{parse_cexp, parse_expressions, parse_program};
+#[cfg(feature = "serde")]
+pub use sexp::{from_str, to_string};
@@ -1,91 +1,174 @@
 use crate::ast::*;
+use crate::backtrace::{SourceId, Span};
+use crate::port::Port;
+use crate::substitution::value_to_literal;
+use crate::value::{SExpression, Value};
+use crate::SchemeError;
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::character::complete::{char, multispace0, multispace1};
-use nom::combinator::{cut, map};
+use nom::bytes::complete::is_not;
+use nom::bytes::complete::{escaped_transform, tag};
+use nom::character::complete::{alpha1, anychar, char, multispace1, none_of};
+use nom::combinator::{all_consuming, cut, map, map_opt, opt, value, verify};
 use nom::error::{ErrorKind, ParseError};
 use nom::multi::{many0, many1};
-use nom::number::complete::double;
 use nom::sequence::{delimited, preceded, separated_pair, terminated, tuple};
 use nom::{AsChar, IResult, InputTakeAtPosition};
+use std::cell::Cell;
+
+thread_local! {
+    // The text being parsed and its length, which spans are measured from.
+    static SOURCE: Cell<Option<(SourceId, usize)>> = const { Cell::new(None) };
+}
+
+/// Runs `parse` on `src` as a text of its own, unless it is part of a text
+/// being parsed already, passing it the id of that text.
+pub(crate) fn in_source<T>(src: &str, parse: impl FnOnce(SourceId) -> T) -> T {
+    if let Some((source, _)) = SOURCE.with(Cell::get) {
+        return parse(source);
+    }
+    let source = SourceId::new();
+    SOURCE.with(|current| current.set(Some((source, src.len()))));
+    let result = parse(source);
+    SOURCE.with(|current| current.set(None));
+    result
+}
+
+// The span from `before` up to `after`, both suffixes of the text being
+// parsed.
+fn span(before: &str, after: &str) -> Option<Span> {
+    SOURCE
+        .with(Cell::get)
+        .map(|(source, len)| Span::new(source, len - before.len(), len - after.len()))
+}
 
 pub fn parse_program(i: &str) -> IResult<&str, Program> {
+    in_source(i, |_| program(i))
+}
+
+fn program(i: &str) -> IResult<&str, Program> {
     let inner = preceded(
-        multispace0,
+        atmosphere,
         map(
             preceded(
-                terminated(tag("L3"), multispace1),
+                terminated(tag("L3"), atmosphere1),
                 cut(many1(parse_expression)),
             ),
             |exps| Program { exps },
         ),
     );
-    preceded(multispace0, delimited(char('('), inner, char(')')))(i)
+    delimited(atmosphere, delimited(char('('), inner, close), atmosphere)(i)
+}
+
+/// Parses top-level expressions that are not wrapped in `(L3 ...)`.
+pub fn parse_expressions(i: &str) -> IResult<&str, Vec<Expression>> {
+    in_source(i, |_| terminated(many1(parse_expression), atmosphere)(i))
 }
 
 fn parse_expression(i: &str) -> IResult<&str, Expression> {
     preceded(
-        multispace0,
+        atmosphere,
         alt((
             parse_define,
-            map(parse_cexp, Expression::ConstituentExpression),
+            parse_define_values,
+            map(cexp, Expression::ConstituentExpression),
         )),
     )(i)
 }
 
 fn parse_define(i: &str) -> IResult<&str, Expression> {
+    let plain = tuple((map(parse_identifier, str::to_owned), cexp));
+    // `(define (f x y) body)` is `(define f (lambda (x y) body))`.
+    let procedure = map(
+        tuple((
+            delimited(
+                char('('),
+                tuple((
+                    preceded(atmosphere, parse_identifier),
+                    many0(map(preceded(atmosphere, parse_identifier), |var: &str| {
+                        VariableDeclaration(var.to_owned())
+                    })),
+                )),
+                close,
+            ),
+            many1(cexp),
+        )),
+        |((var, args), body)| {
+            (
+                var.to_owned(),
+                ConstituentExpression::Procedure(Procedure { args, body }),
+            )
+        },
+    );
+    let inner = map(
+        preceded(
+            terminated(tag("define"), atmosphere1),
+            cut(alt((procedure, plain))),
+        ),
+        |(var, val)| Expression::Define(Define { var, val }),
+    );
+    delimited(char('('), inner, close)(i)
+}
+
+fn parse_define_values(i: &str) -> IResult<&str, Expression> {
     let inner = map(
         preceded(
-            terminated(tag("define"), multispace1),
-            cut(tuple((parse_identifier, parse_cexp))),
+            terminated(tag("define-values"), atmosphere1),
+            cut(tuple((parse_formals, cexp))),
         ),
-        |(var, val)| {
-            Expression::Define(Define {
-                var: var.to_string(),
+        |(vars, val)| {
+            Expression::DefineValues(DefineValues {
+                vars: vars.into_iter().map(|vd| vd.0).collect(),
                 val,
             })
         },
     );
-    delimited(char('('), inner, char(')'))(i)
+    delimited(char('('), inner, close)(i)
 }
 
 pub fn parse_cexp(i: &str) -> IResult<&str, ConstituentExpression> {
+    in_source(i, |_| cexp(i))
+}
+
+fn cexp(i: &str) -> IResult<&str, ConstituentExpression> {
     preceded(
-        multispace0,
+        atmosphere,
         alt((
             parse_primop,
             parse_boolean,
             parse_number,
+            parse_string,
+            parse_char,
+            parse_quoted,
             parse_var,
             parse_if,
             parse_proc,
             parse_let,
+            parse_let_values,
+            parse_receive,
+            parse_guard,
+            parse_delay,
+            parse_cons_stream,
             parse_applic,
         )),
     )(i)
 }
 
 fn parse_applic(i: &str) -> IResult<&str, ConstituentExpression> {
-    let inner = map(
-        tuple((parse_cexp, many0(parse_cexp))),
-        |(operator, operands)| {
-            ConstituentExpression::Applic(Application {
-                operator: Box::from(operator),
-                operands: operands
-                    .iter()
-                    .map(|cexp| Box::from(cexp.clone()))
-                    .collect(),
-            })
-        },
-    );
-    delimited(char('('), inner, char(')'))(i)
+    let inner = tuple((cexp, many0(cexp)));
+    let (rest, (operator, operands)) = delimited(char('('), inner, close)(i)?;
+    let applic = Application {
+        operator: Box::from(operator),
+        operands,
+        span: span(i, rest),
+    };
+    Ok((rest, ConstituentExpression::Applic(applic)))
 }
 
 fn parse_if(i: &str) -> IResult<&str, ConstituentExpression> {
     let inner = map(
         preceded(
-            terminated(tag("if"), multispace1),
-            cut(tuple((parse_cexp, parse_cexp, parse_cexp))),
+            terminated(tag("if"), atmosphere1),
+            cut(tuple((cexp, cexp, cexp))),
         ),
         |(cond, then, alt)| {
             ConstituentExpression::If(If {
@@ -95,47 +178,39 @@ fn parse_if(i: &str) -> IResult<&str, ConstituentExpression> {
             })
         },
     );
-    delimited(char('('), inner, char(')'))(i)
+    delimited(char('('), inner, close)(i)
 }
 
 fn parse_proc(i: &str) -> IResult<&str, ConstituentExpression> {
     let inner = map(
         preceded(
-            terminated(tag("lambda"), multispace1),
-            cut(tuple((
-                delimited(
-                    char('('),
-                    many0(preceded(multispace0, parse_identifier)),
-                    char(')'),
-                ),
-                many1(parse_cexp),
-            ))),
+            terminated(tag("lambda"), atmosphere1),
+            cut(tuple((parse_formals, many1(cexp)))),
         ),
-        |(params, body)| {
-            ConstituentExpression::Procedure(Procedure {
-                args: params
-                    .iter()
-                    .map(|arg| VariableDeclaration(arg.to_owned().to_owned()))
-                    .collect(),
-                body: body.iter().map(|cexp| Box::from(cexp.clone())).collect(),
-            })
-        },
+        |(args, body)| ConstituentExpression::Procedure(Procedure { args, body }),
+    );
+    delimited(char('('), inner, close)(i)
+}
+
+fn parse_formals(i: &str) -> IResult<&str, Vec<VariableDeclaration>> {
+    let inner = delimited(
+        char('('),
+        many0(map(preceded(atmosphere, parse_identifier), |var: &str| {
+            VariableDeclaration(var.to_owned())
+        })),
+        close,
     );
-    delimited(char('('), inner, char(')'))(i)
+    preceded(atmosphere, inner)(i)
 }
 
 fn parse_binding(i: &str) -> IResult<&str, (VariableDeclaration, Box<ConstituentExpression>)> {
     map(
         preceded(
-            multispace0,
+            atmosphere,
             delimited(
                 char('('),
-                separated_pair(
-                    preceded(multispace0, parse_identifier),
-                    multispace1,
-                    parse_cexp,
-                ),
-                char(')'),
+                separated_pair(preceded(atmosphere, parse_identifier), atmosphere1, cexp),
+                close,
             ),
         ),
         |(vd, cexp)| (VariableDeclaration(vd.to_owned()), Box::from(cexp)),
@@ -145,33 +220,211 @@ fn parse_binding(i: &str) -> IResult<&str, (VariableDeclaration, Box<Constituent
 fn parse_let(i: &str) -> IResult<&str, ConstituentExpression> {
     let inner = map(
         preceded(
-            terminated(tag("let"), multispace1),
+            terminated(tag("let"), atmosphere1),
             cut(tuple((
-                delimited(char('('), many0(parse_binding), char(')')),
-                many1(parse_cexp),
+                delimited(char('('), many0(parse_binding), close),
+                many1(cexp),
             ))),
         ),
-        |(bindings, body)| {
-            ConstituentExpression::Let(Let {
-                bindings,
-                body: body.iter().map(|cexp| Box::from(cexp.clone())).collect(),
+        |(bindings, body)| ConstituentExpression::Let(Let { bindings, body }),
+    );
+    delimited(char('('), inner, close)(i)
+}
+
+type ValuesBinding = (Vec<VariableDeclaration>, Box<ConstituentExpression>);
+
+fn parse_values_binding(i: &str) -> IResult<&str, ValuesBinding> {
+    map(
+        preceded(
+            atmosphere,
+            delimited(char('('), tuple((parse_formals, cexp)), close),
+        ),
+        |(formals, cexp)| (formals, Box::from(cexp)),
+    )(i)
+}
+
+fn parse_let_values(i: &str) -> IResult<&str, ConstituentExpression> {
+    let keyword = alt((
+        value(false, tag("let-values")),
+        value(true, tag("let*-values")),
+    ));
+    let inner = map(
+        tuple((
+            terminated(keyword, atmosphere1),
+            cut(tuple((
+                delimited(char('('), many0(parse_values_binding), close),
+                many1(cexp),
+            ))),
+        )),
+        |(sequential, (bindings, body))| {
+            if sequential {
+                let_star_values(bindings, body)
+            } else {
+                ConstituentExpression::LetValues(LetValues { bindings, body })
+            }
+        },
+    );
+    delimited(char('('), inner, close)(i)
+}
+
+fn parse_receive(i: &str) -> IResult<&str, ConstituentExpression> {
+    let inner = map(
+        preceded(
+            terminated(tag("receive"), atmosphere1),
+            cut(tuple((parse_formals, cexp, many1(cexp)))),
+        ),
+        |(formals, cexp, body)| {
+            ConstituentExpression::LetValues(LetValues {
+                bindings: vec![(formals, Box::from(cexp))],
+                body,
+            })
+        },
+    );
+    delimited(char('('), inner, close)(i)
+}
+
+/// Nests one `let-values` per binding, so each binding sees the previous ones.
+fn let_star_values(
+    bindings: Vec<ValuesBinding>,
+    body: Vec<ConstituentExpression>,
+) -> ConstituentExpression {
+    let mut bindings = bindings.into_iter().rev();
+    let innermost = LetValues {
+        bindings: bindings.next().into_iter().collect(),
+        body,
+    };
+    bindings.fold(
+        ConstituentExpression::LetValues(innermost),
+        |inner, binding| {
+            ConstituentExpression::LetValues(LetValues {
+                bindings: vec![binding],
+                body: vec![inner],
+            })
+        },
+    )
+}
+
+fn parse_guard(i: &str) -> IResult<&str, ConstituentExpression> {
+    let inner = map(
+        preceded(
+            terminated(tag("guard"), atmosphere1),
+            cut(tuple((
+                delimited(
+                    char('('),
+                    tuple((
+                        preceded(atmosphere, parse_identifier),
+                        many0(parse_cond_clause),
+                    )),
+                    close,
+                ),
+                many1(cexp),
+            ))),
+        ),
+        |((var, clauses), body)| {
+            ConstituentExpression::Guard(Guard {
+                var: VariableDeclaration(var.to_owned()),
+                clauses,
+                body,
             })
         },
     );
-    delimited(char('('), inner, char(')'))(i)
+    delimited(char('('), inner, close)(i)
+}
+
+fn parse_delay(i: &str) -> IResult<&str, ConstituentExpression> {
+    let keyword = alt((value(true, tag("delay-force")), value(false, tag("delay"))));
+    let inner = map(
+        tuple((terminated(keyword, atmosphere1), cut(cexp))),
+        |(lazy, expr)| delay(expr, lazy),
+    );
+    delimited(char('('), inner, close)(i)
+}
+
+fn parse_cons_stream(i: &str) -> IResult<&str, ConstituentExpression> {
+    let inner = map(
+        preceded(
+            terminated(tag("cons-stream"), atmosphere1),
+            cut(tuple((cexp, cexp))),
+        ),
+        |(first, rest)| cons_stream(first, rest),
+    );
+    delimited(char('('), inner, close)(i)
+}
+
+fn delay(expr: ConstituentExpression, lazy: bool) -> ConstituentExpression {
+    ConstituentExpression::Delay(Delay {
+        expr: Box::from(expr),
+        lazy,
+    })
+}
+
+/// `(cons-stream a b)` is `(cons a (delay b))`.
+fn cons_stream(first: ConstituentExpression, rest: ConstituentExpression) -> ConstituentExpression {
+    ConstituentExpression::Applic(Application {
+        operator: Box::from(ConstituentExpression::PrimitiveOperation(
+            PrimitiveOperation::Cons,
+        )),
+        operands: vec![first, delay(rest, false)],
+        span: None,
+    })
+}
+
+fn parse_cond_clause(i: &str) -> IResult<&str, CondClause> {
+    let else_clause = map(
+        preceded(terminated(tag("else"), atmosphere1), many1(cexp)),
+        |body| CondClause {
+            test: None,
+            body,
+            arrow: false,
+        },
+    );
+    let arrow_clause = map(
+        separated_pair(
+            cexp,
+            preceded(atmosphere, terminated(tag("=>"), atmosphere1)),
+            cexp,
+        ),
+        |(test, receiver)| CondClause {
+            test: Some(Box::from(test)),
+            body: vec![receiver],
+            arrow: true,
+        },
+    );
+    let test_clause = map(tuple((cexp, many0(cexp))), |(test, body)| CondClause {
+        test: Some(Box::from(test)),
+        body,
+        arrow: false,
+    });
+    preceded(
+        atmosphere,
+        delimited(
+            char('('),
+            alt((else_clause, arrow_clause, test_clause)),
+            close,
+        ),
+    )(i)
 }
 
 fn parse_number(i: &str) -> IResult<&str, ConstituentExpression> {
-    map(double, |x: f64| ConstituentExpression::Number(Number(x)))(i)
+    map(parse_number_literal, |x| {
+        ConstituentExpression::Number(Number(x))
+    })(i)
 }
 
 fn parse_boolean(i: &str) -> IResult<&str, ConstituentExpression> {
-    alt((
-        map(tag("#t"), |_| ConstituentExpression::Boolean(Boolean(true))),
-        map(tag("#f"), |_| {
-            ConstituentExpression::Boolean(Boolean(false))
-        }),
-    ))(i)
+    map(parse_boolean_literal, |b| {
+        ConstituentExpression::Boolean(Boolean(b))
+    })(i)
+}
+
+fn parse_string(i: &str) -> IResult<&str, ConstituentExpression> {
+    map(parse_string_literal, |s| ConstituentExpression::Str(Str(s)))(i)
+}
+
+fn parse_char(i: &str) -> IResult<&str, ConstituentExpression> {
+    map(parse_char_literal, |c| {
+        ConstituentExpression::Literal(Value::Char(c))
+    })(i)
 }
 
 fn parse_var(i: &str) -> IResult<&str, ConstituentExpression> {
@@ -181,82 +434,393 @@ fn parse_var(i: &str) -> IResult<&str, ConstituentExpression> {
 }
 
 fn parse_quoted(i: &str) -> IResult<&str, ConstituentExpression> {
-    todo!()
+    let inner = preceded(terminated(tag("quote"), atmosphere1), cut(parse_datum));
+    map(
+        alt((
+            preceded(char('\''), cut(parse_datum)),
+            delimited(char('('), inner, close),
+        )),
+        |datum| value_to_literal(&datum),
+    )(i)
 }
 
 fn parse_primop(i: &str) -> IResult<&str, ConstituentExpression> {
-    use PrimitiveOperation::*;
-    let parse_math_op = alt((
-        map(tag("+"), |_| ConstituentExpression::PrimitiveOperation(Add)),
-        map(tag("-"), |_| ConstituentExpression::PrimitiveOperation(Sub)),
-        map(tag("*"), |_| ConstituentExpression::PrimitiveOperation(Mul)),
-        map(tag("/"), |_| ConstituentExpression::PrimitiveOperation(Div)),
-        map(tag("="), |_| {
-            ConstituentExpression::PrimitiveOperation(Equal)
-        }),
-        map(tag("<"), |_| {
-            ConstituentExpression::PrimitiveOperation(LessThan)
-        }),
-        map(tag("<="), |_| {
-            ConstituentExpression::PrimitiveOperation(LessThanOrEqual)
-        }),
-        map(tag(">"), |_| {
-            ConstituentExpression::PrimitiveOperation(GreaterThan)
-        }),
-        map(tag(">="), |_| {
-            ConstituentExpression::PrimitiveOperation(GreaterThanOrEqual)
-        }),
-    ));
-    let parse_logical_op = alt((
-        map(tag("not"), |_| {
-            ConstituentExpression::PrimitiveOperation(Not)
-        }),
-        map(tag("and"), |_| {
-            ConstituentExpression::PrimitiveOperation(And)
-        }),
-        map(tag("or"), |_| ConstituentExpression::PrimitiveOperation(Or)),
-    ));
-    let parse_pair_op = alt((
-        map(tag("cons"), |_| {
-            ConstituentExpression::PrimitiveOperation(Cons)
-        }),
-        map(tag("car"), |_| {
-            ConstituentExpression::PrimitiveOperation(Car)
-        }),
-        map(tag("cdr"), |_| {
-            ConstituentExpression::PrimitiveOperation(Cdr)
-        }),
-        map(tag("list"), |_| {
-            ConstituentExpression::PrimitiveOperation(List)
-        }),
-    ));
-    let parse_predicate = alt((
-        map(tag("eq?"), |_| {
-            ConstituentExpression::PrimitiveOperation(IsEq)
-        }),
-        map(tag("pair?"), |_| {
-            ConstituentExpression::PrimitiveOperation(IsPair)
-        }),
-        map(tag("number?"), |_| {
-            ConstituentExpression::PrimitiveOperation(IsNumber)
-        }),
-        map(tag("boolean?"), |_| {
-            ConstituentExpression::PrimitiveOperation(IsBoolean)
+    map_opt(parse_identifier, |name: &str| {
+        PrimitiveOperation::from_name(name).map(ConstituentExpression::PrimitiveOperation)
+    })(i)
+}
+
+pub(crate) fn parse_datum(i: &str) -> IResult<&str, Value> {
+    preceded(
+        atmosphere,
+        alt((
+            map(parse_boolean_literal, Value::Boolean),
+            map(parse_number_literal, Value::Number),
+            map(parse_string_literal, Value::String),
+            map(parse_char_literal, Value::Char),
+            map(preceded(char('\''), cut(parse_datum)), |datum| {
+                Value::list(vec![Value::symbol("quote"), datum])
+            }),
+            parse_list_datum,
+            map(parse_identifier, Value::symbol),
+        )),
+    )(i)
+}
+
+/// Converts a datum (e.g. a quoted list) into the expression it denotes, so it
+/// can be evaluated by `eval`.
+pub(crate) fn datum_to_cexp(datum: &Value) -> Result<ConstituentExpression, SchemeError> {
+    match datum {
+        Value::Number(n) => Ok(ConstituentExpression::Number(Number(*n))),
+        Value::Boolean(b) => Ok(ConstituentExpression::Boolean(Boolean(*b))),
+        Value::String(s) => Ok(ConstituentExpression::Str(Str(s.clone()))),
+        Value::SExpression(sexpr) => match &**sexpr {
+            SExpression::Symbol(name) => Ok(match PrimitiveOperation::from_name(name) {
+                Some(primop) => ConstituentExpression::PrimitiveOperation(primop),
+                None => ConstituentExpression::VariableReference(VariableReference(name.clone())),
+            }),
+            SExpression::Nil => Err(syntax_error("Empty application", datum)),
+            SExpression::Compound(head, _) => {
+                let items = datum
+                    .list_items()
+                    .ok_or_else(|| syntax_error("Improper list in expression", datum))?;
+                match (head.as_symbol(), &items[1..]) {
+                    (Some("quote"), [quoted]) => Ok(value_to_literal(quoted)),
+                    (Some("if"), [cond, then, alt]) => Ok(ConstituentExpression::If(If {
+                        cond: Box::from(datum_to_cexp(cond)?),
+                        then: Box::from(datum_to_cexp(then)?),
+                        alt: Box::from(datum_to_cexp(alt)?),
+                    })),
+                    (Some("lambda"), [params, body @ ..]) if !body.is_empty() => {
+                        Ok(ConstituentExpression::Procedure(Procedure {
+                            args: datum_to_params(params)?,
+                            body: datums_to_cexps(body)?,
+                        }))
+                    }
+                    (Some("let"), [bindings, body @ ..]) if !body.is_empty() => {
+                        Ok(ConstituentExpression::Let(Let {
+                            bindings: datum_to_bindings(bindings)?,
+                            body: datums_to_cexps(body)?,
+                        }))
+                    }
+                    (Some("let-values"), [bindings, body @ ..]) if !body.is_empty() => {
+                        Ok(ConstituentExpression::LetValues(LetValues {
+                            bindings: datum_to_values_bindings(bindings)?,
+                            body: datums_to_cexps(body)?,
+                        }))
+                    }
+                    (Some("let*-values"), [bindings, body @ ..]) if !body.is_empty() => {
+                        Ok(let_star_values(
+                            datum_to_values_bindings(bindings)?,
+                            datums_to_cexps(body)?,
+                        ))
+                    }
+                    (Some("receive"), [formals, val, body @ ..]) if !body.is_empty() => {
+                        Ok(ConstituentExpression::LetValues(LetValues {
+                            bindings: vec![(
+                                datum_to_params(formals)?,
+                                Box::from(datum_to_cexp(val)?),
+                            )],
+                            body: datums_to_cexps(body)?,
+                        }))
+                    }
+                    (Some("delay"), [expr]) => Ok(delay(datum_to_cexp(expr)?, false)),
+                    (Some("delay-force"), [expr]) => Ok(delay(datum_to_cexp(expr)?, true)),
+                    (Some("cons-stream"), [first, rest]) => {
+                        Ok(cons_stream(datum_to_cexp(first)?, datum_to_cexp(rest)?))
+                    }
+                    (Some("guard"), [spec, body @ ..]) if !body.is_empty() => {
+                        let spec = spec.list_items().unwrap_or_default();
+                        match spec.split_first() {
+                            Some((var, clauses)) if var.as_symbol().is_some() => {
+                                Ok(ConstituentExpression::Guard(Guard {
+                                    var: VariableDeclaration(var.as_symbol().unwrap().to_owned()),
+                                    clauses: clauses
+                                        .iter()
+                                        .map(datum_to_clause)
+                                        .collect::<Result<_, _>>()?,
+                                    body: datums_to_cexps(body)?,
+                                }))
+                            }
+                            _ => Err(syntax_error("Malformed guard", datum)),
+                        }
+                    }
+                    (
+                        Some(
+                            "quote" | "if" | "lambda" | "let" | "let-values" | "let*-values"
+                            | "receive" | "guard" | "delay" | "delay-force" | "cons-stream"
+                            | "define" | "define-values",
+                        ),
+                        _,
+                    ) => Err(syntax_error("Malformed special form", datum)),
+                    (_, operands) => Ok(ConstituentExpression::Applic(Application {
+                        operator: Box::from(datum_to_cexp(head)?),
+                        operands: datums_to_cexps(operands)?,
+                        span: None,
+                    })),
+                }
+            }
+        },
+        _ => Ok(value_to_literal(datum)),
+    }
+}
+
+fn datums_to_cexps(datums: &[Value]) -> Result<Vec<ConstituentExpression>, SchemeError> {
+    datums.iter().map(datum_to_cexp).collect()
+}
+
+fn datum_to_params(datum: &Value) -> Result<Vec<VariableDeclaration>, SchemeError> {
+    datum
+        .list_items()
+        .and_then(|params| {
+            params
+                .iter()
+                .map(|p| p.as_symbol().map(|s| VariableDeclaration(s.to_owned())))
+                .collect()
+        })
+        .ok_or_else(|| syntax_error("Malformed parameter list", datum))
+}
+
+fn datum_to_clause(datum: &Value) -> Result<CondClause, SchemeError> {
+    match datum.list_items().as_deref() {
+        Some([test, body @ ..]) if test.as_symbol() == Some("else") && !body.is_empty() => {
+            Ok(CondClause {
+                test: None,
+                body: datums_to_cexps(body)?,
+                arrow: false,
+            })
+        }
+        Some([test, arrow, receiver]) if arrow.as_symbol() == Some("=>") => Ok(CondClause {
+            test: Some(Box::from(datum_to_cexp(test)?)),
+            body: vec![datum_to_cexp(receiver)?],
+            arrow: true,
         }),
-        map(tag("symbol?"), |_| {
-            ConstituentExpression::PrimitiveOperation(IsSymbol)
+        Some([test, body @ ..]) => Ok(CondClause {
+            test: Some(Box::from(datum_to_cexp(test)?)),
+            body: datums_to_cexps(body)?,
+            arrow: false,
         }),
-    ));
+        _ => Err(syntax_error("Malformed clause", datum)),
+    }
+}
+
+fn datum_to_bindings(
+    datum: &Value,
+) -> Result<Vec<(VariableDeclaration, Box<ConstituentExpression>)>, SchemeError> {
+    let bindings = datum
+        .list_items()
+        .ok_or_else(|| syntax_error("Malformed let bindings", datum))?;
+    bindings
+        .iter()
+        .map(|binding| match binding.list_items().as_deref() {
+            Some([var, val]) => match var.as_symbol() {
+                Some(var) => Ok((
+                    VariableDeclaration(var.to_owned()),
+                    Box::from(datum_to_cexp(val)?),
+                )),
+                None => Err(syntax_error("Malformed let binding", binding)),
+            },
+            _ => Err(syntax_error("Malformed let binding", binding)),
+        })
+        .collect()
+}
+
+fn datum_to_values_bindings(datum: &Value) -> Result<Vec<ValuesBinding>, SchemeError> {
+    let bindings = datum
+        .list_items()
+        .ok_or_else(|| syntax_error("Malformed let-values bindings", datum))?;
+    bindings
+        .iter()
+        .map(|binding| match binding.list_items().as_deref() {
+            Some([formals, val]) => Ok((datum_to_params(formals)?, Box::from(datum_to_cexp(val)?))),
+            _ => Err(syntax_error("Malformed let-values binding", binding)),
+        })
+        .collect()
+}
+
+fn syntax_error(message: &str, datum: &Value) -> SchemeError {
+    SchemeError::SyntaxError(format!("{}: {}", message, datum))
+}
+
+/// Reads the next datum from `port`, consuming only the characters that make
+/// it up. Returns `None` at end of input.
+pub(crate) fn read_datum(port: &Port) -> Result<Option<Value>, SchemeError> {
+    skip_atmosphere(port)?;
+    if port.peek_char()?.is_none() {
+        return Ok(None);
+    }
+    let mut text = String::new();
+    collect_datum(port, &mut text)?;
+    let parsed = all_consuming(terminated(parse_datum, atmosphere))(text.as_str());
+    match parsed {
+        Ok((_, datum)) => Ok(Some(datum)),
+        Err(_) => Err(SchemeError::ReadError(format!("Malformed datum: {}", text))),
+    }
+}
+
+fn skip_atmosphere(port: &Port) -> Result<(), SchemeError> {
+    while let Some(c) = port.peek_char()? {
+        if c == ';' {
+            while !matches!(port.read_char()?, None | Some('\n')) {}
+        } else if c.is_whitespace() {
+            port.read_char()?;
+        } else {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn next_char(port: &Port) -> Result<char, SchemeError> {
+    port.read_char()?
+        .ok_or_else(|| SchemeError::ReadError("Unexpected end of input".to_owned()))
+}
+
+fn collect_datum(port: &Port, text: &mut String) -> Result<(), SchemeError> {
+    match next_char(port)? {
+        '(' => {
+            text.push('(');
+            loop {
+                skip_atmosphere(port)?;
+                if port.peek_char()? == Some(')') {
+                    port.read_char()?;
+                    text.push(')');
+                    return Ok(());
+                }
+                text.push(' ');
+                collect_datum(port, text)?;
+            }
+        }
+        ')' => Err(SchemeError::ReadError("Unexpected ')'".to_owned())),
+        '\'' => {
+            text.push('\'');
+            skip_atmosphere(port)?;
+            collect_datum(port, text)
+        }
+        '"' => {
+            text.push('"');
+            loop {
+                let c = next_char(port)?;
+                text.push(c);
+                match c {
+                    '"' => return Ok(()),
+                    '\\' => text.push(next_char(port)?),
+                    _ => {}
+                }
+            }
+        }
+        c => {
+            text.push(c);
+            if c == '#' && port.peek_char()? == Some('\\') {
+                text.push(next_char(port)?);
+                text.push(next_char(port)?);
+            }
+            while let Some(c) = port.peek_char()? {
+                if c.is_whitespace() || "()\";".contains(c) {
+                    break;
+                }
+                text.push(c);
+                port.read_char()?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn parse_list_datum(i: &str) -> IResult<&str, Value> {
+    let dot = preceded(atmosphere, terminated(char('.'), atmosphere1));
+    let inner = tuple((many0(parse_datum), opt(preceded(dot, cut(parse_datum)))));
+    map(delimited(char('('), inner, close), |(items, tail)| {
+        Value::improper_list(items, tail.unwrap_or_else(Value::nil))
+    })(i)
+}
+
+fn parse_number_literal(i: &str) -> IResult<&str, f64> {
+    map_opt(parse_token, |token: &str| {
+        // The R7RS spellings of the numbers that are not finite.
+        match token {
+            "+inf.0" => return Some(f64::INFINITY),
+            "-inf.0" => return Some(f64::NEG_INFINITY),
+            "+nan.0" | "-nan.0" => return Some(f64::NAN),
+            _ => {}
+        }
+        let mut chars = token.chars();
+        let starts_numeric = match chars.next() {
+            Some('+' | '-' | '.') => chars.next().is_some_and(|c| c.is_ascii_digit() || c == '.'),
+            Some(c) => c.is_ascii_digit(),
+            None => false,
+        };
+        if starts_numeric {
+            token.parse::<f64>().ok()
+        } else {
+            None
+        }
+    })(i)
+}
 
+fn parse_boolean_literal(i: &str) -> IResult<&str, bool> {
     alt((
-        parse_math_op,
-        parse_logical_op,
-        parse_pair_op,
-        parse_predicate,
+        value(true, tag("#true")),
+        value(false, tag("#false")),
+        value(true, tag("#t")),
+        value(false, tag("#f")),
     ))(i)
 }
 
-fn parse_identifier<T, E: ParseError<T>>(input: T) -> IResult<T, T, E>
+fn parse_string_literal(i: &str) -> IResult<&str, String> {
+    let escape = alt((
+        value("\\", char('\\')),
+        value("\"", char('"')),
+        value("\n", char('n')),
+        value("\t", char('t')),
+        value("\r", char('r')),
+    ));
+    let contents = map(
+        opt(escaped_transform(none_of("\\\""), '\\', escape)),
+        Option::unwrap_or_default,
+    );
+    delimited(char('"'), contents, cut(char('"')))(i)
+}
+
+fn parse_char_literal(i: &str) -> IResult<&str, char> {
+    let named = map_opt(alpha1, |name: &str| match name {
+        "space" => Some(' '),
+        "newline" | "linefeed" => Some('\n'),
+        "tab" => Some('\t'),
+        "return" => Some('\r'),
+        "null" | "nul" => Some('\0'),
+        _ if name.chars().count() == 1 => name.chars().next(),
+        _ => None,
+    });
+    preceded(tag("#\\"), cut(alt((named, anychar))))(i)
+}
+
+// Whitespace and `;` comments, which run to the end of the line, as skipped
+// between the data read from a port.
+fn atmosphere(i: &str) -> IResult<&str, ()> {
+    value((), many0(alt((multispace1, comment))))(i)
+}
+
+// Atmosphere separating a keyword from what follows it.
+fn atmosphere1(i: &str) -> IResult<&str, ()> {
+    value((), many1(alt((multispace1, comment))))(i)
+}
+
+fn comment(i: &str) -> IResult<&str, &str> {
+    preceded(char(';'), map(opt(is_not("\n")), Option::unwrap_or_default))(i)
+}
+
+// The `)` closing a form, after any atmosphere.
+fn close(i: &str) -> IResult<&str, char> {
+    preceded(atmosphere, char(')'))(i)
+}
+
+// A lone `.` is the dot of a dotted pair, not a name.
+fn parse_identifier(i: &str) -> IResult<&str, &str> {
+    verify(parse_token, |s: &str| s != ".")(i)
+}
+
+fn parse_token<T, E: ParseError<T>>(input: T) -> IResult<T, T, E>
 where
     T: InputTakeAtPosition,
     <T as InputTakeAtPosition>::Item: AsChar,
@@ -264,8 +828,30 @@ where
     input.split_at_position1_complete(
         |item| {
             let c = item.as_char();
-            c != '-' && c != '?' && c != '!' && !c.is_alphanum()
+            !c.is_alphanum() && !"!$%&*/:<=>?^_~+-.@".contains(c)
         },
         ErrorKind::AlphaNumeric,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_lone_dot_is_not_an_identifier() {
+        assert!(parse_program("(L3 (lambda (a . rest) rest))").is_err());
+        assert!(parse_program("(L3 (define (f . x) x))").is_err());
+        assert!(parse_program("(L3 (define ... 1) (quote (a . b)))").is_ok());
+    }
+
+    #[test]
+    fn comments_and_whitespace_are_skipped() {
+        let src = "; program\n(L3 (define ; name\n x 1) (+ x ; one more\n 1 ) ) ; end";
+        let (rest, program) = parse_program(src).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(program.exps.len(), 2);
+        let (rest, exps) = parse_expressions("(lambda (x) x ) ;; identity\n'(a ;b\n c)").unwrap();
+        assert_eq!((rest, exps.len()), ("", 2));
+    }
+}
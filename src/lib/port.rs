@@ -0,0 +1,256 @@
+use crate::shared::{Lock, SendSync, Shared};
+use crate::value::Value;
+use crate::SchemeError;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Cursor, Read, Write};
+
+/// A Scheme port, backed by a Rust `Read` or `Write` trait object.
+/// Clones share the same underlying stream.
+#[derive(Clone)]
+pub struct Port(Shared<Lock<PortInner>>);
+
+struct PortInner {
+    kind: PortKind,
+    closed: bool,
+}
+
+enum PortKind {
+    Input(InputPort),
+    Output(Box<Writer>),
+    StringOutput(String),
+}
+
+struct InputPort {
+    reader: Box<Reader>,
+    chars: VecDeque<char>,
+    pending: Vec<u8>,
+    eof: bool,
+}
+
+#[cfg(not(feature = "sync"))]
+type Reader = dyn Read;
+#[cfg(feature = "sync")]
+type Reader = dyn Read + Send;
+#[cfg(not(feature = "sync"))]
+type Writer = dyn Write;
+#[cfg(feature = "sync")]
+type Writer = dyn Write + Send;
+
+impl InputPort {
+    fn fill(&mut self) -> io::Result<()> {
+        let mut buf = [0; 4096];
+        while self.chars.is_empty() && !self.eof {
+            let n = self.reader.read(&mut buf)?;
+            if n == 0 {
+                self.eof = true;
+                if !self.pending.is_empty() {
+                    self.chars.push_back(char::REPLACEMENT_CHARACTER);
+                    self.pending.clear();
+                }
+            } else {
+                self.pending.extend_from_slice(&buf[..n]);
+                self.decode_pending();
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_pending(&mut self) {
+        loop {
+            match std::str::from_utf8(&self.pending) {
+                Ok(s) => {
+                    self.chars.extend(s.chars());
+                    self.pending.clear();
+                    return;
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    let s = std::str::from_utf8(&self.pending[..valid]).unwrap_or_default();
+                    self.chars.extend(s.chars());
+                    match e.error_len() {
+                        Some(len) => {
+                            self.chars.push_back(char::REPLACEMENT_CHARACTER);
+                            self.pending.drain(..valid + len);
+                        }
+                        None => {
+                            self.pending.drain(..valid);
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn peek(&mut self) -> io::Result<Option<char>> {
+        self.fill()?;
+        Ok(self.chars.front().copied())
+    }
+
+    fn next(&mut self) -> io::Result<Option<char>> {
+        self.fill()?;
+        Ok(self.chars.pop_front())
+    }
+}
+
+impl Port {
+    fn new(kind: PortKind) -> Port {
+        Port(Shared::new(Lock::new(PortInner {
+            kind,
+            closed: false,
+        })))
+    }
+
+    pub fn from_reader(reader: impl Read + SendSync + 'static) -> Port {
+        Port::new(PortKind::Input(InputPort {
+            reader: Box::new(reader),
+            chars: VecDeque::new(),
+            pending: Vec::new(),
+            eof: false,
+        }))
+    }
+
+    pub fn from_writer(writer: impl Write + SendSync + 'static) -> Port {
+        Port::new(PortKind::Output(Box::new(writer)))
+    }
+
+    pub fn input_string(s: &str) -> Port {
+        Port::from_reader(Cursor::new(s.to_owned().into_bytes()))
+    }
+
+    pub fn output_string() -> Port {
+        Port::new(PortKind::StringOutput(String::new()))
+    }
+
+    pub fn is_input(&self) -> bool {
+        matches!(self.0.borrow().kind, PortKind::Input(_))
+    }
+
+    pub fn is_output(&self) -> bool {
+        !self.is_input()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.0.borrow().closed
+    }
+
+    /// The text written so far to a port created by `Port::output_string`.
+    pub fn output_string_contents(&self) -> Option<String> {
+        match &self.0.borrow().kind {
+            PortKind::StringOutput(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn close(&self) -> Result<(), SchemeError> {
+        let mut inner = self.0.borrow_mut();
+        if !inner.closed {
+            inner.closed = true;
+            if let PortKind::Output(writer) = &mut inner.kind {
+                writer.flush().map_err(io_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read_char(&self) -> Result<Option<char>, SchemeError> {
+        self.with_input(InputPort::next)
+    }
+
+    pub(crate) fn peek_char(&self) -> Result<Option<char>, SchemeError> {
+        self.with_input(InputPort::peek)
+    }
+
+    fn with_input<T>(
+        &self,
+        f: impl FnOnce(&mut InputPort) -> io::Result<T>,
+    ) -> Result<T, SchemeError> {
+        let mut inner = self.0.borrow_mut();
+        if inner.closed {
+            return Err(SchemeError::IoError {
+                message: "Port is closed".to_owned(),
+                source: None,
+            });
+        }
+        match &mut inner.kind {
+            PortKind::Input(input) => f(input).map_err(io_error),
+            _ => Err(SchemeError::type_mismatch(
+                "an input port",
+                &Value::Port(self.clone()),
+            )),
+        }
+    }
+
+    pub(crate) fn write_str(&self, s: &str) -> Result<(), SchemeError> {
+        let mut inner = self.0.borrow_mut();
+        if inner.closed {
+            return Err(SchemeError::IoError {
+                message: "Port is closed".to_owned(),
+                source: None,
+            });
+        }
+        match &mut inner.kind {
+            PortKind::Output(writer) => writer
+                .write_all(s.as_bytes())
+                .and_then(|_| writer.flush())
+                .map_err(io_error),
+            PortKind::StringOutput(buf) => {
+                buf.push_str(s);
+                Ok(())
+            }
+            PortKind::Input(_) => Err(SchemeError::type_mismatch(
+                "an output port",
+                &Value::Port(self.clone()),
+            )),
+        }
+    }
+}
+
+impl fmt::Debug for Port {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_input() {
+            write!(f, "#<input-port>")
+        } else {
+            write!(f, "#<output-port>")
+        }
+    }
+}
+
+fn io_error(e: io::Error) -> SchemeError {
+    SchemeError::io("I/O error".to_owned(), e)
+}
+
+thread_local! {
+    static CURRENT_INPUT: RefCell<Port> = RefCell::new(Port::from_reader(io::stdin()));
+    static CURRENT_OUTPUT: RefCell<Port> = RefCell::new(Port::from_writer(io::stdout()));
+    static CURRENT_ERROR: RefCell<Port> = RefCell::new(Port::from_writer(io::stderr()));
+}
+
+pub fn current_input_port() -> Port {
+    CURRENT_INPUT.with(|port| port.borrow().clone())
+}
+
+pub fn current_output_port() -> Port {
+    CURRENT_OUTPUT.with(|port| port.borrow().clone())
+}
+
+pub fn current_error_port() -> Port {
+    CURRENT_ERROR.with(|port| port.borrow().clone())
+}
+
+/// Replaces the current input port of this thread, returning the previous one.
+pub fn set_current_input_port(port: Port) -> Port {
+    CURRENT_INPUT.with(|current| current.replace(port))
+}
+
+/// Replaces the current output port of this thread, returning the previous one.
+pub fn set_current_output_port(port: Port) -> Port {
+    CURRENT_OUTPUT.with(|current| current.replace(port))
+}
+
+/// Replaces the current error port of this thread, returning the previous one.
+pub fn set_current_error_port(port: Port) -> Port {
+    CURRENT_ERROR.with(|current| current.replace(port))
+}
@@ -1,8 +1,13 @@
 use crate::ast::*;
+use crate::env::Environment;
 use crate::eval::is_true;
-use crate::value::{SExpression, Value};
-use crate::SchemeError;
+use crate::port::Port;
+use crate::shared::Shared;
+use crate::value::{Promise, PromiseState, SExpression, Value};
+use crate::{parser, port, Arity, SchemeError};
+use std::fs::File;
 use std::ops::Neg;
+use std::path::Path;
 
 pub fn apply_primitive(primop: &PrimitiveOperation, args: &[Value]) -> Result<Value, SchemeError> {
     match primop {
@@ -27,102 +32,128 @@ pub fn apply_primitive(primop: &PrimitiveOperation, args: &[Value]) -> Result<Va
         PrimitiveOperation::IsNumber => is_number(args),
         PrimitiveOperation::IsBoolean => is_boolean(args),
         PrimitiveOperation::IsSymbol => is_symbol(args),
+        PrimitiveOperation::Display => display(args),
+        PrimitiveOperation::Write => write(args),
+        PrimitiveOperation::WriteString => write_string(args),
+        PrimitiveOperation::Newline => newline(args),
+        PrimitiveOperation::WriteChar => write_char(args),
+        PrimitiveOperation::ReadChar => read_char(args),
+        PrimitiveOperation::PeekChar => peek_char(args),
+        PrimitiveOperation::ReadLine => read_line(args),
+        PrimitiveOperation::ReadString => read_string(args),
+        PrimitiveOperation::Read => read(args),
+        PrimitiveOperation::CurrentInputPort => current_port(args, port::current_input_port),
+        PrimitiveOperation::CurrentOutputPort => current_port(args, port::current_output_port),
+        PrimitiveOperation::CurrentErrorPort => current_port(args, port::current_error_port),
+        PrimitiveOperation::OpenInputFile => open_input_file(args),
+        PrimitiveOperation::OpenOutputFile => open_output_file(args),
+        PrimitiveOperation::OpenInputString => open_input_string(args),
+        PrimitiveOperation::OpenOutputString => open_output_string(args),
+        PrimitiveOperation::GetOutputString => get_output_string(args),
+        PrimitiveOperation::ClosePort
+        | PrimitiveOperation::CloseInputPort
+        | PrimitiveOperation::CloseOutputPort => close_port(args),
+        PrimitiveOperation::MakePromise => make_promise(args),
+        PrimitiveOperation::IsPromise => is_promise(args),
+        PrimitiveOperation::IsForeign => is_foreign(args),
+        PrimitiveOperation::StreamCar => car(args),
+        PrimitiveOperation::Values => Ok(Value::values(args.to_vec())),
+        PrimitiveOperation::EofObject => eof_object(args),
+        PrimitiveOperation::IsEofObject => is_eof_object(args),
+        PrimitiveOperation::IsEnvironment => is_environment(args),
+        PrimitiveOperation::IsErrorObject => is_error_object(args),
+        PrimitiveOperation::ErrorObjectMessage => error_object_message(args),
+        PrimitiveOperation::ErrorObjectIrritants => error_object_irritants(args),
+        PrimitiveOperation::IsFileError => {
+            is_error_from(args, |e| matches!(e, SchemeError::IoError { .. }))
+        }
+        PrimitiveOperation::IsReadError => {
+            is_error_from(args, |e| matches!(e, SchemeError::ReadError(_)))
+        }
+        PrimitiveOperation::CallWithOutputString
+        | PrimitiveOperation::WithOutputToFile
+        | PrimitiveOperation::Eval
+        | PrimitiveOperation::InteractionEnvironment
+        | PrimitiveOperation::SchemeReportEnvironment
+        | PrimitiveOperation::NullEnvironment
+        | PrimitiveOperation::Error
+        | PrimitiveOperation::Raise
+        | PrimitiveOperation::RaiseContinuable
+        | PrimitiveOperation::WithExceptionHandler
+        | PrimitiveOperation::CallCC
+        | PrimitiveOperation::CallWithCurrentContinuation
+        | PrimitiveOperation::DynamicWind
+        | PrimitiveOperation::CallWithValues
+        | PrimitiveOperation::Force
+        | PrimitiveOperation::StreamCdr
+        | PrimitiveOperation::Breakpoint => {
+            unreachable!("{} is applied by the evaluator", primop.name())
+        }
     }
 }
 
 fn add(args: &[Value]) -> Result<Value, SchemeError> {
     args.iter()
-        .fold(Ok(Value::Number(0.0)), |acc, cur| match (acc, cur) {
-            (Ok(Value::Number(sum)), Value::Number(x)) => Ok(Value::Number(sum + x)),
-            _ => Err(SchemeError::TypeMismatch(format!(
-                "Value {:?} not a number",
-                cur
-            ))),
+        .try_fold(Value::Number(0.0), |acc, cur| match (acc, cur) {
+            (Value::Number(sum), Value::Number(x)) => Ok(Value::Number(sum + x)),
+            _ => Err(SchemeError::type_mismatch("a number", cur)),
         })
 }
 
 fn sub(args: &[Value]) -> Result<Value, SchemeError> {
     match args {
-        [] => Err(SchemeError::ArgumentMismatch(
-            "Expected at least 1 argument, got 0.".to_owned(),
-        )),
+        [] => Err(SchemeError::arity(Arity::AtLeast(1), 0)),
         [Value::Number(x)] => Ok(Value::Number(x.neg())),
-        [v] => Err(SchemeError::TypeMismatch(format!(
-            "Value {:?} not a number",
-            v
-        ))),
+        [v] => Err(SchemeError::type_mismatch("a number", v)),
         [v @ Value::Number(_), rest @ ..] => {
             rest.iter()
-                .fold(Ok(v.clone()), |acc, cur| match (acc, cur) {
-                    (Ok(Value::Number(diff)), Value::Number(x)) => Ok(Value::Number(diff - x)),
-                    _ => Err(SchemeError::TypeMismatch(format!(
-                        "Value {:?} not a number",
-                        cur
-                    ))),
+                .try_fold(v.clone(), |acc, cur| match (acc, cur) {
+                    (Value::Number(diff), Value::Number(x)) => Ok(Value::Number(diff - x)),
+                    _ => Err(SchemeError::type_mismatch("a number", cur)),
                 })
         }
-        [v, ..] => Err(SchemeError::TypeMismatch(format!(
-            "Value {:?} not a number",
-            v
-        ))),
+        [v, ..] => Err(SchemeError::type_mismatch("a number", v)),
     }
 }
 
 fn mul(args: &[Value]) -> Result<Value, SchemeError> {
     args.iter()
-        .fold(Ok(Value::Number(1.0)), |acc, cur| match (acc, cur) {
-            (Ok(Value::Number(prod)), Value::Number(x)) => Ok(Value::Number(prod * x)),
-            _ => Err(SchemeError::TypeMismatch(format!(
-                "Value {:?} not a number",
-                cur
-            ))),
+        .try_fold(Value::Number(1.0), |acc, cur| match (acc, cur) {
+            (Value::Number(prod), Value::Number(x)) => Ok(Value::Number(prod * x)),
+            _ => Err(SchemeError::type_mismatch("a number", cur)),
         })
 }
 
 fn div(args: &[Value]) -> Result<Value, SchemeError> {
     match args {
-        [] => Err(SchemeError::ArgumentMismatch(
-            "Expected at least 1 argument, got 0.".to_owned(),
-        )),
+        [] => Err(SchemeError::arity(Arity::AtLeast(1), 0)),
         [Value::Number(x)] => Ok(Value::Number(1.0 / x)),
-        [v] => Err(SchemeError::TypeMismatch(format!(
-            "Value {:?} not a number",
-            v
-        ))),
+        [v] => Err(SchemeError::type_mismatch("a number", v)),
         [v @ Value::Number(_), rest @ ..] => {
             rest.iter()
-                .fold(Ok(v.clone()), |acc, cur| match (acc, cur) {
-                    (Ok(Value::Number(frac)), Value::Number(x)) => Ok(Value::Number(frac / x)),
-                    _ => Err(SchemeError::TypeMismatch(format!(
-                        "Value {:?} not a number",
-                        cur
-                    ))),
+                .try_fold(v.clone(), |acc, cur| match (acc, cur) {
+                    (Value::Number(frac), Value::Number(x)) => Ok(Value::Number(frac / x)),
+                    _ => Err(SchemeError::type_mismatch("a number", cur)),
                 })
         }
-        [v, ..] => Err(SchemeError::TypeMismatch(format!(
-            "Value {:?} not a number",
-            v
-        ))),
+        [v, ..] => Err(SchemeError::type_mismatch("a number", v)),
     }
 }
 
 fn compare(args: &[Value], cmp_fn: fn(&f64, &f64) -> bool) -> Result<Value, SchemeError> {
     match args {
-        [] => Err(SchemeError::ArgumentMismatch(
-            "Expected at least 1 argument, got 0.".to_owned(),
-        )),
+        [] => Err(SchemeError::arity(Arity::AtLeast(1), 0)),
         [Value::Number(_)] => Ok(Value::Boolean(true)),
-        _ => args.windows(2).fold(Ok(Value::Boolean(true)), |acc, cur| {
+        _ => args.windows(2).try_fold(Value::Boolean(true), |acc, cur| {
             let lhs = &cur[0];
             let rhs = &cur[1];
             match (acc, lhs, rhs) {
-                (Ok(Value::Boolean(b)), Value::Number(lhs), Value::Number(rhs)) => {
+                (Value::Boolean(b), Value::Number(lhs), Value::Number(rhs)) => {
                     Ok(Value::Boolean(b && cmp_fn(lhs, rhs)))
                 }
-                _ => Err(SchemeError::TypeMismatch(format!(
-                    "Value {:?} not a number",
-                    cur
-                ))),
+                (_, Value::Number(_), v) | (_, v, _) => {
+                    Err(SchemeError::type_mismatch("a number", v))
+                }
             }
         }),
     }
@@ -131,10 +162,7 @@ fn compare(args: &[Value], cmp_fn: fn(&f64, &f64) -> bool) -> Result<Value, Sche
 fn not(args: &[Value]) -> Result<Value, SchemeError> {
     match args {
         [v] => Ok(Value::Boolean(!is_true(v))),
-        _ => Err(SchemeError::ArgumentMismatch(format!(
-            "Expected 1 argument, got {}",
-            args.len()
-        ))),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
     }
 }
 
@@ -161,23 +189,21 @@ fn or(args: &[Value]) -> Result<Value, SchemeError> {
 
 fn is_eq(args: &[Value]) -> Result<Value, SchemeError> {
     if args.len() != 2 {
-        Err(SchemeError::ArgumentMismatch(format!(
-            "Expected 2 arguments, got {}",
-            args.len()
-        )))
+        Err(SchemeError::arity(Arity::Exactly(2), args.len()))
     } else {
         let arg1 = &args[0];
         let arg2 = &args[1];
         match (arg1, arg2) {
             (Value::Number(x), Value::Number(y)) => Ok(Value::Boolean(x == y)),
             (Value::Boolean(x), Value::Boolean(y)) => Ok(Value::Boolean(x == y)),
-            (Value::SExpression(x), Value::SExpression(y)) => {
-                if x.is_empty() && y.is_empty() {
-                    Ok(Value::Boolean(true))
-                } else {
-                    Ok(Value::Boolean(false))
-                }
-            }
+            (Value::Char(x), Value::Char(y)) => Ok(Value::Boolean(x == y)),
+            (Value::Eof, Value::Eof) | (Value::Void, Value::Void) => Ok(Value::Boolean(true)),
+            (Value::Foreign(x), Value::Foreign(y)) => Ok(Value::Boolean(x.is_eq(y))),
+            (Value::SExpression(x), Value::SExpression(y)) => match (&**x, &**y) {
+                (SExpression::Nil, SExpression::Nil) => Ok(Value::Boolean(true)),
+                (SExpression::Symbol(x), SExpression::Symbol(y)) => Ok(Value::Boolean(x == y)),
+                _ => Ok(Value::Boolean(false)),
+            },
             _ => Ok(Value::Boolean(false)),
         }
     }
@@ -185,14 +211,8 @@ fn is_eq(args: &[Value]) -> Result<Value, SchemeError> {
 
 fn cons(args: &[Value]) -> Result<Value, SchemeError> {
     match args {
-        [v1, v2] => Ok(Value::SExpression(Box::from(SExpression::Compound(
-            v1.clone(),
-            v2.clone(),
-        )))),
-        _ => Err(SchemeError::ArgumentMismatch(format!(
-            "Expected 2 arguments, got {}",
-            args.len()
-        ))),
+        [v1, v2] => Ok(Value::cons(v1.clone(), v2.clone())),
+        _ => Err(SchemeError::arity(Arity::Exactly(2), args.len())),
     }
 }
 
@@ -200,19 +220,10 @@ fn car(args: &[Value]) -> Result<Value, SchemeError> {
     match args {
         [Value::SExpression(sexpr)] => match &**sexpr {
             SExpression::Compound(first, _) => Ok(first.clone()),
-            _ => Err(SchemeError::TypeMismatch(format!(
-                "Expected a compound s-expression, got {:?}",
-                **sexpr
-            ))),
+            _ => Err(SchemeError::type_mismatch("a pair", &args[0])),
         },
-        [v] => Err(SchemeError::TypeMismatch(format!(
-            "Expected a compound s-expression, got {:?}",
-            v
-        ))),
-        _ => Err(SchemeError::ArgumentMismatch(format!(
-            "Expected 2 arguments, got {}",
-            args.len()
-        ))),
+        [v] => Err(SchemeError::type_mismatch("a pair", v)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
     }
 }
 
@@ -220,32 +231,15 @@ fn cdr(args: &[Value]) -> Result<Value, SchemeError> {
     match args {
         [Value::SExpression(sexpr)] => match &**sexpr {
             SExpression::Compound(_, second) => Ok(second.clone()),
-            _ => Err(SchemeError::TypeMismatch(format!(
-                "Expected a compound s-expression, got {:?}",
-                **sexpr
-            ))),
+            _ => Err(SchemeError::type_mismatch("a pair", &args[0])),
         },
-        [v] => Err(SchemeError::TypeMismatch(format!(
-            "Expected a compound s-expression, got {:?}",
-            v
-        ))),
-        _ => Err(SchemeError::ArgumentMismatch(format!(
-            "Expected 2 arguments, got {}",
-            args.len()
-        ))),
+        [v] => Err(SchemeError::type_mismatch("a pair", v)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
     }
 }
 
 fn list(args: &[Value]) -> Result<Value, SchemeError> {
-    match args {
-        [] => Ok(Value::SExpression(Box::from(SExpression::Nil))),
-        _ => {
-            let lst = args.iter().rev().fold(SExpression::Nil, |acc, cur| {
-                SExpression::Compound(cur.clone(), Value::SExpression(Box::from(acc)))
-            });
-            Ok(Value::SExpression(Box::from(lst)))
-        }
-    }
+    Ok(Value::list(args.to_vec()))
 }
 
 fn is_pair(args: &[Value]) -> Result<Value, SchemeError> {
@@ -257,10 +251,7 @@ fn is_pair(args: &[Value]) -> Result<Value, SchemeError> {
             },
             _ => Ok(Value::Boolean(false)),
         },
-        _ => Err(SchemeError::ArgumentMismatch(format!(
-            "Expected 1 argument, got {}",
-            args.len()
-        ))),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
     }
 }
 
@@ -268,10 +259,7 @@ fn is_number(args: &[Value]) -> Result<Value, SchemeError> {
     match args {
         [Value::Number(_)] => Ok(Value::Boolean(true)),
         [_] => Ok(Value::Boolean(false)),
-        _ => Err(SchemeError::ArgumentMismatch(format!(
-            "Expected 1 argument, got {}",
-            args.len()
-        ))),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
     }
 }
 
@@ -279,10 +267,7 @@ fn is_boolean(args: &[Value]) -> Result<Value, SchemeError> {
     match args {
         [Value::Boolean(_)] => Ok(Value::Boolean(true)),
         [_] => Ok(Value::Boolean(false)),
-        _ => Err(SchemeError::ArgumentMismatch(format!(
-            "Expected 1 argument, got {}",
-            args.len()
-        ))),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
     }
 }
 
@@ -295,9 +280,281 @@ fn is_symbol(args: &[Value]) -> Result<Value, SchemeError> {
             },
             _ => Ok(Value::Boolean(false)),
         },
-        _ => Err(SchemeError::ArgumentMismatch(format!(
-            "Expected 1 argument, got {}",
-            args.len()
-        ))),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn output_port(args: &[Value]) -> Result<Port, SchemeError> {
+    match args {
+        [] => Ok(port::current_output_port()),
+        [Value::Port(p)] if p.is_output() => Ok(p.clone()),
+        [v] => Err(SchemeError::type_mismatch("an output port", v)),
+        _ => Err(SchemeError::arity(Arity::AtMost(1), args.len())),
+    }
+}
+
+fn input_port(args: &[Value]) -> Result<Port, SchemeError> {
+    match args {
+        [] => Ok(port::current_input_port()),
+        [Value::Port(p)] if p.is_input() => Ok(p.clone()),
+        [v] => Err(SchemeError::type_mismatch("an input port", v)),
+        _ => Err(SchemeError::arity(Arity::AtMost(1), args.len())),
+    }
+}
+
+fn display(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [v, rest @ ..] => {
+            output_port(rest)?.write_str(&v.display().to_string())?;
+            Ok(Value::Void)
+        }
+        _ => Err(SchemeError::arity(Arity::AtLeast(1), 0)),
+    }
+}
+
+fn write(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [v, rest @ ..] => {
+            output_port(rest)?.write_str(&v.to_string())?;
+            Ok(Value::Void)
+        }
+        _ => Err(SchemeError::arity(Arity::AtLeast(1), 0)),
+    }
+}
+
+fn write_string(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::String(s), rest @ ..] => {
+            output_port(rest)?.write_str(s)?;
+            Ok(Value::Void)
+        }
+        [v, ..] => Err(SchemeError::type_mismatch("a string", v)),
+        _ => Err(SchemeError::arity(Arity::AtLeast(1), 0)),
+    }
+}
+
+fn write_char(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Char(c), rest @ ..] => {
+            output_port(rest)?.write_str(c.encode_utf8(&mut [0; 4]))?;
+            Ok(Value::Void)
+        }
+        [v, ..] => Err(SchemeError::type_mismatch("a character", v)),
+        _ => Err(SchemeError::arity(Arity::AtLeast(1), 0)),
+    }
+}
+
+fn newline(args: &[Value]) -> Result<Value, SchemeError> {
+    output_port(args)?.write_str("\n")?;
+    Ok(Value::Void)
+}
+
+fn read_char(args: &[Value]) -> Result<Value, SchemeError> {
+    Ok(input_port(args)?
+        .read_char()?
+        .map_or(Value::Eof, Value::Char))
+}
+
+fn peek_char(args: &[Value]) -> Result<Value, SchemeError> {
+    Ok(input_port(args)?
+        .peek_char()?
+        .map_or(Value::Eof, Value::Char))
+}
+
+fn read_line(args: &[Value]) -> Result<Value, SchemeError> {
+    let port = input_port(args)?;
+    let mut line = String::new();
+    loop {
+        match port.read_char()? {
+            None if line.is_empty() => return Ok(Value::Eof),
+            None | Some('\n') => return Ok(Value::String(line)),
+            Some(c) => line.push(c),
+        }
+    }
+}
+
+fn read_string(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Number(k), rest @ ..] if *k >= 0.0 && k.fract() == 0.0 => {
+            let port = input_port(rest)?;
+            let mut s = String::new();
+            for _ in 0..*k as usize {
+                match port.read_char()? {
+                    Some(c) => s.push(c),
+                    None => break,
+                }
+            }
+            if s.is_empty() && *k > 0.0 {
+                Ok(Value::Eof)
+            } else {
+                Ok(Value::String(s))
+            }
+        }
+        [v, ..] => Err(SchemeError::type_mismatch("a non-negative integer", v)),
+        _ => Err(SchemeError::arity(Arity::AtLeast(1), 0)),
+    }
+}
+
+fn read(args: &[Value]) -> Result<Value, SchemeError> {
+    Ok(parser::read_datum(&input_port(args)?)?.unwrap_or(Value::Eof))
+}
+
+fn current_port(args: &[Value], current: fn() -> Port) -> Result<Value, SchemeError> {
+    match args {
+        [] => Ok(Value::Port(current())),
+        _ => Err(SchemeError::arity(Arity::Exactly(0), args.len())),
+    }
+}
+
+fn string_arg(args: &[Value]) -> Result<&str, SchemeError> {
+    match args {
+        [Value::String(s)] => Ok(s),
+        [v] => Err(SchemeError::type_mismatch("a string", v)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn open_input_file(args: &[Value]) -> Result<Value, SchemeError> {
+    let path = string_arg(args)?;
+    open_file(path, Path::new(path))
+}
+
+// Opens `file` for reading, which `path` names in errors.
+pub(crate) fn open_file(path: &str, file: &Path) -> Result<Value, SchemeError> {
+    let file = File::open(file).map_err(|e| SchemeError::io(format!("Cannot open {}", path), e))?;
+    Ok(Value::Port(Port::from_reader(file)))
+}
+
+pub(crate) fn open_output_file(args: &[Value]) -> Result<Value, SchemeError> {
+    let path = string_arg(args)?;
+    let file =
+        File::create(path).map_err(|e| SchemeError::io(format!("Cannot open {}", path), e))?;
+    Ok(Value::Port(Port::from_writer(file)))
+}
+
+fn open_input_string(args: &[Value]) -> Result<Value, SchemeError> {
+    Ok(Value::Port(Port::input_string(string_arg(args)?)))
+}
+
+fn open_output_string(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [] => Ok(Value::Port(Port::output_string())),
+        _ => Err(SchemeError::arity(Arity::Exactly(0), args.len())),
+    }
+}
+
+fn get_output_string(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Port(p)] => match p.output_string_contents() {
+            Some(s) => Ok(Value::String(s)),
+            None => Err(SchemeError::type_mismatch(
+                "a port created by open-output-string",
+                &args[0],
+            )),
+        },
+        [v] => Err(SchemeError::type_mismatch("a port", v)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn close_port(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Port(p)] => {
+            p.close()?;
+            Ok(Value::Void)
+        }
+        [v] => Err(SchemeError::type_mismatch("a port", v)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn make_promise(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Promise(p)] => Ok(Value::Promise(p.clone())),
+        [v] => Ok(Value::Promise(Promise::new(PromiseState::Done(v.clone())))),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn is_promise(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [v] => Ok(Value::Boolean(matches!(v, Value::Promise(_)))),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn is_foreign(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [v] => Ok(Value::Boolean(matches!(v, Value::Foreign(_)))),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn eof_object(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [] => Ok(Value::Eof),
+        _ => Err(SchemeError::arity(Arity::Exactly(0), args.len())),
+    }
+}
+
+fn is_eof_object(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Eof] => Ok(Value::Boolean(true)),
+        [_] => Ok(Value::Boolean(false)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+// Primitive procedures are part of L3's syntax rather than bindings, so both
+// standard environments are `initial`, the interaction environment without the
+// program's definitions, and have the same primitives.
+pub(crate) fn standard_environment(
+    args: &[Value],
+    initial: Shared<Environment>,
+) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Number(version)] if *version == 7.0 => Ok(Value::Environment(initial)),
+        [v] => Err(SchemeError::type_mismatch("the report version 7", v)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn is_environment(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Environment(_)] => Ok(Value::Boolean(true)),
+        [_] => Ok(Value::Boolean(false)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn is_error_object(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Error(_)] => Ok(Value::Boolean(true)),
+        [_] => Ok(Value::Boolean(false)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn error_object_message(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Error(err)] => Ok(Value::String(err.message.clone())),
+        [v] => Err(SchemeError::type_mismatch("an error object", v)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn error_object_irritants(args: &[Value]) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Error(err)] => Ok(Value::list(err.irritants.clone())),
+        [v] => Err(SchemeError::type_mismatch("an error object", v)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
+    }
+}
+
+fn is_error_from(args: &[Value], pred: fn(&SchemeError) -> bool) -> Result<Value, SchemeError> {
+    match args {
+        [Value::Error(err)] => Ok(Value::Boolean(err.origin.as_deref().is_some_and(pred))),
+        [_] => Ok(Value::Boolean(false)),
+        _ => Err(SchemeError::arity(Arity::Exactly(1), args.len())),
     }
 }
@@ -0,0 +1,314 @@
+use super::{Error, Result};
+use crate::convert::{self, FromValue};
+use crate::parser::parse_datum;
+use crate::value::{SExpression, Value};
+use crate::{ParseError, SchemeError};
+use nom::character::complete::multispace0;
+use nom::combinator::all_consuming;
+use nom::sequence::delimited;
+use serde::de::{self, DeserializeOwned, Visitor};
+
+/// Deserializes a `T` from the text of an S-expression.
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T> {
+    let (_, value) =
+        all_consuming(delimited(multispace0, parse_datum, multispace0))(s).map_err(|e| {
+            let e = SchemeError::ParseError(ParseError::new(s, e));
+            let (line, col) = e.span().map_or((1, 1), |span| span.line_col(s));
+            let message = format!("{} at {}:{}", e, line, col);
+            Error::from(e).with_message(message)
+        })?;
+    from_value(value)
+}
+
+/// Deserializes a `T` from a Scheme value.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+    T::deserialize(Deserializer::new(value))
+}
+
+/// Reads Rust data out of a Scheme value.
+pub struct Deserializer {
+    value: Value,
+    // Whether the value is that of a struct field, which is only present
+    // when it is not `None`.
+    field: bool,
+}
+
+impl Deserializer {
+    pub fn new(value: Value) -> Deserializer {
+        Deserializer {
+            value,
+            field: false,
+        }
+    }
+
+    fn convert<T: FromValue>(&self) -> Result<T> {
+        Ok(T::from_value(&self.value)?)
+    }
+
+    fn list(self) -> Result<ListAccess> {
+        match self.value.list_items() {
+            Some(items) => Ok(ListAccess {
+                items: items.into_iter(),
+                index: 0,
+            }),
+            None => Err(SchemeError::type_mismatch("a list", &self.value).into()),
+        }
+    }
+
+    fn alist(self, fields: bool) -> Result<AlistAccess> {
+        let pairs = convert::alist(&self.value)?;
+        Ok(AlistAccess {
+            pairs: pairs.into_iter(),
+            value: None,
+            fields,
+        })
+    }
+}
+
+macro_rules! deserialize_numbers {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(self.convert::<$ty>()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match &self.value {
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 9007199254740992.0 => {
+                visitor.visit_i64(*n as i64)
+            }
+            Value::Number(n) => visitor.visit_f64(*n),
+            Value::Boolean(b) => visitor.visit_bool(*b),
+            Value::Char(c) => visitor.visit_char(*c),
+            Value::String(s) => visitor.visit_str(s),
+            Value::SExpression(sexpr) => match &**sexpr {
+                SExpression::Symbol(name) => visitor.visit_str(name),
+                SExpression::Nil => visitor.visit_unit(),
+                SExpression::Compound(..) => self.deserialize_seq(visitor),
+            },
+            Value::Void => visitor.visit_unit(),
+            value => Err(SchemeError::type_mismatch("data", value).into()),
+        }
+    }
+
+    deserialize_numbers! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_bool => visit_bool(bool),
+        deserialize_char => visit_char(char),
+        deserialize_str => visit_string(String),
+        deserialize_string => visit_string(String),
+        deserialize_identifier => visit_string(String),
+        deserialize_bytes => visit_byte_buf(Vec<u8>),
+        deserialize_byte_buf => visit_byte_buf(Vec<u8>),
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Boolean(false) if !self.field => visitor.visit_none(),
+            value => visitor.visit_some(Deserializer::new(value)),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match &self.value {
+            Value::Void => visitor.visit_unit(),
+            Value::SExpression(sexpr) if sexpr.is_empty() => visitor.visit_unit(),
+            value => Err(SchemeError::type_mismatch("()", value).into()),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if self.value.as_symbol() == Some(name) {
+            return visitor.visit_unit();
+        }
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(self.list()?)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(self.alist(false)?)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_map(self.alist(true)?)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let (variant, fields) = convert::tagged(&self.value)?;
+        visitor.visit_enum(EnumAccess { variant, fields })
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+struct ListAccess {
+    items: std::vec::IntoIter<Value>,
+    // The index of the next item.
+    index: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for ListAccess {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        let Some(item) = self.items.next() else {
+            return Ok(None);
+        };
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(Deserializer::new(item))
+            .map(Some)
+            .map_err(|e| e.in_context(format!("element {}", index)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct AlistAccess {
+    pairs: std::vec::IntoIter<(Value, Value)>,
+    // The pair whose key was just read.
+    value: Option<(Value, Value)>,
+    // Whether the pairs are the fields of a struct rather than map entries.
+    fields: bool,
+}
+
+impl<'de> de::MapAccess<'de> for AlistAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.pairs.next() {
+            Some((key, value)) => {
+                let result = seed.deserialize(Deserializer::new(key.clone()));
+                self.value = Some((key, value));
+                result.map(Some).map_err(|e| e.in_context("key".to_owned()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| Error::new("next_value called before next_key"))?;
+        let deserializer = Deserializer {
+            value,
+            field: self.fields,
+        };
+        seed.deserialize(deserializer).map_err(|e| {
+            let context = match key.as_symbol() {
+                Some(name) if self.fields => format!("field {}", name),
+                _ => key.to_string(),
+            };
+            e.in_context(context)
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.pairs.len())
+    }
+}
+
+// A variant name and the list of its fields, `()` for a unit variant.
+struct EnumAccess {
+    variant: String,
+    fields: Value,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = Error;
+    type Variant = Deserializer;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Deserializer)> {
+        let variant = seed.deserialize(Deserializer::new(Value::symbol(&self.variant)))?;
+        Ok((variant, Deserializer::new(self.fields)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        de::Deserializer::deserialize_unit(self, de::IgnoredAny).map(|_| ())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        let items = convert::list(&self.value, 1)?;
+        seed.deserialize(Deserializer::new(items.into_iter().next().unwrap()))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_struct(self, "", fields, visitor)
+    }
+}
@@ -0,0 +1,101 @@
+//! A serde data format that writes Rust data as Scheme S-expressions.
+//!
+//! Structs are association lists of their fields, sequences and tuples are
+//! lists, maps are association lists, `None` is `#f` and enum variants are
+//! lists tagged with the variant name, or just the name for a unit variant:
+//!
+//! ```text
+//! ((port . 8080) (hosts "a" "b") (mode Tls (cert . "c.pem")))
+//! ```
+//!
+//! A struct field that is `None` is left out instead, and a field that is
+//! present is `Some`, so `Some(false)` in an `Option<bool>` field round-trips.
+//! In lists and map values `#f` is always `None`.
+
+mod de;
+mod ser;
+
+pub use de::{from_str, from_value, Deserializer};
+pub use ser::{to_string, to_value, Serializer};
+
+use crate::backtrace::Span;
+use crate::SchemeError;
+use std::fmt;
+
+/// A serialization error, with the fields, keys and elements that lead to the
+/// failing part of the data, outermost first.
+#[derive(Debug, Clone)]
+pub struct Error {
+    message: String,
+    path: Vec<String>,
+    source: Option<Box<SchemeError>>,
+}
+
+impl Error {
+    fn new(message: impl fmt::Display) -> Error {
+        Error {
+            message: message.to_string(),
+            path: vec![],
+            source: None,
+        }
+    }
+
+    /// Where in the data the error happened, e.g. `["field hosts", "element
+    /// 1"]`.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// Where in the text the error happened, for text that could not be read.
+    pub fn span(&self) -> Option<Span> {
+        self.source.as_ref().and_then(|e| e.span())
+    }
+
+    fn with_message(self, message: String) -> Error {
+        Error { message, ..self }
+    }
+
+    fn in_context(mut self, context: String) -> Error {
+        self.path.insert(0, context);
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.path {
+            write!(f, "{}: ", step)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|e| e as _)
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::new(msg)
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::new(msg)
+    }
+}
+
+impl From<SchemeError> for Error {
+    fn from(e: SchemeError) -> Error {
+        Error {
+            message: e.to_string(),
+            path: vec![],
+            source: Some(Box::from(e)),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,60 @@
+//! The reference counting behind values: `Rc` by default, and `Arc` with the
+//! `sync` feature, which makes values and interpreters `Send + Sync` so that
+//! a loaded interpreter can be cloned into worker threads.
+//!
+//! Closure bodies and S-expressions sit behind `Shared` too, so cloning a
+//! value never copies a syntax tree. Applying a closure does: its arguments
+//! are substituted into a fresh copy of the body.
+
+#[cfg(not(feature = "sync"))]
+pub use std::rc::Rc as Shared;
+#[cfg(feature = "sync")]
+pub use std::sync::Arc as Shared;
+
+/// `Send + Sync` with the `sync` feature, and implemented by every type
+/// without it. Host functions and foreign objects must implement it.
+#[cfg(not(feature = "sync"))]
+pub trait SendSync {}
+#[cfg(not(feature = "sync"))]
+impl<T: ?Sized> SendSync for T {}
+
+/// `Send + Sync` with the `sync` feature, and implemented by every type
+/// without it. Host functions and foreign objects must implement it.
+#[cfg(feature = "sync")]
+pub trait SendSync: Send + Sync {}
+#[cfg(feature = "sync")]
+impl<T: Send + Sync + ?Sized> SendSync for T {}
+
+#[cfg(not(feature = "sync"))]
+pub(crate) use std::cell::RefCell as Lock;
+
+/// A `Mutex` with the interface of the `RefCell` it replaces.
+#[cfg(feature = "sync")]
+pub(crate) struct Lock<T>(std::sync::Mutex<T>);
+
+#[cfg(feature = "sync")]
+impl<T> Lock<T> {
+    pub(crate) fn new(value: T) -> Lock<T> {
+        Lock(std::sync::Mutex::new(value))
+    }
+
+    // A panic while the lock was held leaves the value as it was, which is
+    // no worse than the `RefCell` case.
+    pub(crate) fn borrow(&self) -> std::sync::MutexGuard<'_, T> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub(crate) fn borrow_mut(&self) -> std::sync::MutexGuard<'_, T> {
+        self.borrow()
+    }
+}
+
+// Stops the build if something reachable from a value is not thread-safe.
+#[cfg(feature = "sync")]
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<crate::value::Value>();
+    assert_send_sync::<crate::Interpreter>();
+};
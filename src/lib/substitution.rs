@@ -20,8 +20,9 @@ fn replace(
                 operands: applic
                     .operands
                     .iter()
-                    .map(|x| Box::from(replace(x, var_gen)))
+                    .map(|x| replace(x, var_gen))
                     .collect(),
+                span: applic.span,
             };
             ConstituentExpression::Applic(applic)
         }
@@ -35,32 +36,106 @@ fn replace(
                 alt: Box::from(alt),
             })
         }
-        ConstituentExpression::Let(_) => {
-            unreachable!("let expressions don't exist at this point")
+        ConstituentExpression::Let(letexp) => {
+            let old_vars: Vec<_> = letexp.bindings.iter().map(|(vd, _)| vd.0.clone()).collect();
+            let new_vars: Vec<_> = old_vars.iter().map(|v| var_gen(v)).collect();
+            let bindings = new_vars
+                .iter()
+                .zip(letexp.bindings.iter())
+                .map(|(v, (_, val))| {
+                    (
+                        VariableDeclaration(v.to_string()),
+                        Box::from(replace(val, var_gen)),
+                    )
+                })
+                .collect();
+            let new_body: Vec<_> = letexp.body.iter().map(|x| replace(x, var_gen)).collect();
+            ConstituentExpression::Let(Let {
+                bindings,
+                body: substitute(&new_body, &old_vars, &make_var_refs(&new_vars)),
+            })
         }
+        ConstituentExpression::LetValues(letexp) => {
+            let old_vars: Vec<_> = letexp
+                .bindings
+                .iter()
+                .flat_map(|(formals, _)| formals.iter().map(|vd| vd.0.clone()))
+                .collect();
+            let new_vars: Vec<_> = old_vars.iter().map(|v| var_gen(v)).collect();
+            let mut new_decls = new_vars.iter().map(|v| VariableDeclaration(v.to_string()));
+            let bindings = letexp
+                .bindings
+                .iter()
+                .map(|(formals, val)| {
+                    (
+                        new_decls.by_ref().take(formals.len()).collect(),
+                        Box::from(replace(val, var_gen)),
+                    )
+                })
+                .collect();
+            let new_body: Vec<_> = letexp.body.iter().map(|x| replace(x, var_gen)).collect();
+            ConstituentExpression::LetValues(LetValues {
+                bindings,
+                body: substitute(&new_body, &old_vars, &make_var_refs(&new_vars)),
+            })
+        }
+        ConstituentExpression::Delay(delay) => ConstituentExpression::Delay(Delay {
+            expr: Box::from(replace(&delay.expr, var_gen)),
+            lazy: delay.lazy,
+        }),
         ConstituentExpression::Procedure(proc) => {
             let old_args: Vec<_> = proc.args.iter().map(|vd| vd.0.to_owned()).collect();
             let new_args: Vec<_> = old_args.iter().map(|v| var_gen(v)).collect();
-            let new_body: Vec<_> = proc
-                .body
-                .iter()
-                .map(|x| Box::from(replace(x, var_gen)))
-                .collect();
+            let new_body: Vec<_> = proc.body.iter().map(|x| replace(x, var_gen)).collect();
             ConstituentExpression::Procedure(Procedure {
                 args: new_args
                     .iter()
                     .map(|v| VariableDeclaration(v.to_string()))
                     .collect(),
-                body: new_body,
+                body: substitute(&new_body, &old_args, &make_var_refs(&new_args)),
+            })
+        }
+        ConstituentExpression::Guard(guard) => {
+            let old_var = vec![guard.var.0.clone()];
+            let new_var = vec![var_gen(&guard.var.0)];
+            let new_refs = make_var_refs(&new_var);
+            ConstituentExpression::Guard(Guard {
+                var: VariableDeclaration(new_var[0].clone()),
+                clauses: guard
+                    .clauses
+                    .iter()
+                    .map(|clause| {
+                        let clause = replace_clause(clause, var_gen);
+                        substitute_clause(&clause, &old_var, &new_refs)
+                    })
+                    .collect(),
+                body: guard.body.iter().map(|x| replace(x, var_gen)).collect(),
             })
         }
         _ => cexp.clone(),
     }
 }
 
-pub(crate) fn rename(exps: &[Box<ConstituentExpression>]) -> Vec<Box<ConstituentExpression>> {
+fn replace_clause(clause: &CondClause, var_gen: &mut impl FnMut(&str) -> String) -> CondClause {
+    CondClause {
+        test: clause
+            .test
+            .as_ref()
+            .map(|test| Box::from(replace(test, var_gen))),
+        body: clause.body.iter().map(|x| replace(x, var_gen)).collect(),
+        arrow: clause.arrow,
+    }
+}
+
+fn make_var_refs(vars: &[String]) -> Vec<ConstituentExpression> {
+    vars.iter()
+        .map(|v| ConstituentExpression::VariableReference(VariableReference(v.to_string())))
+        .collect()
+}
+
+pub(crate) fn rename(exps: &[ConstituentExpression]) -> Vec<ConstituentExpression> {
     exps.iter()
-        .map(|x| Box::from(replace(x, &mut make_var_gen())))
+        .map(|x| replace(x, &mut make_var_gen()))
         .collect()
 }
 
@@ -68,12 +143,13 @@ pub(crate) fn value_to_literal(value: &Value) -> ConstituentExpression {
     match value {
         Value::Number(n) => ConstituentExpression::Number(Number(*n)),
         Value::Boolean(b) => ConstituentExpression::Boolean(Boolean(*b)),
-        Value::PrimitiveOperation(p) => ConstituentExpression::PrimitiveOperation(p.clone()),
+        Value::String(s) => ConstituentExpression::Str(Str(s.clone())),
+        Value::PrimitiveOperation(p) => ConstituentExpression::PrimitiveOperation(*p),
         Value::Closure(c) => ConstituentExpression::Procedure(Procedure {
             args: c.params.to_vec(),
             body: c.body.to_vec(),
         }),
-        Value::SExpression(s) => ConstituentExpression::Literal(*s.to_owned()),
+        _ => ConstituentExpression::Literal(value.clone()),
     }
 }
 
@@ -91,9 +167,9 @@ fn substitute_one(
             }
         }
         ConstituentExpression::If(ifexp) => {
-            let cond = substitute_one(&*ifexp.cond, vars, exps);
-            let then = substitute_one(&*ifexp.then, vars, exps);
-            let alt = substitute_one(&*ifexp.alt, vars, exps);
+            let cond = substitute_one(&ifexp.cond, vars, exps);
+            let then = substitute_one(&ifexp.then, vars, exps);
+            let alt = substitute_one(&ifexp.alt, vars, exps);
             ConstituentExpression::If(If {
                 cond: Box::from(cond),
                 then: Box::from(then),
@@ -105,42 +181,114 @@ fn substitute_one(
             let operands = applic
                 .operands
                 .iter()
-                .map(|x| Box::from(substitute_one(x, vars, exps)))
+                .map(|x| substitute_one(x, vars, exps))
                 .collect();
-            ConstituentExpression::Applic(Application { operator, operands })
+            ConstituentExpression::Applic(Application {
+                operator,
+                operands,
+                span: applic.span,
+            })
         }
         ConstituentExpression::Procedure(proc) => {
             let args: Vec<_> = proc.args.iter().map(|a| a.0.to_owned()).collect();
-            let subst = vars.iter().zip(exps.iter());
-            let free_subst: Vec<_> = subst.filter(|(v, _)| !args.contains(v)).collect();
-            let (vars, exps): (Vec<_>, Vec<_>) = free_subst.iter().cloned().unzip();
+            let (vars, exps) = unbound(vars, exps, &args);
             ConstituentExpression::Procedure(Procedure {
                 args: proc.args.to_vec(),
-                body: substitute(
-                    &proc.body.to_vec(),
-                    vars.iter()
-                        .map(|s| s.to_owned().clone())
-                        .collect::<Vec<_>>()
-                        .as_slice(),
-                    exps.iter()
-                        .map(|e| e.to_owned().clone())
-                        .collect::<Vec<_>>()
-                        .as_slice(),
-                )
+                body: substitute(&proc.body, &vars, &exps),
+            })
+        }
+        ConstituentExpression::Let(letexp) => {
+            let let_vars: Vec<_> = letexp.bindings.iter().map(|(vd, _)| vd.0.clone()).collect();
+            let bindings = letexp
+                .bindings
                 .iter()
-                .map(|cexp| Box::from(cexp.clone()))
-                .collect(),
+                .map(|(vd, val)| (vd.clone(), Box::from(substitute_one(val, vars, exps))))
+                .collect();
+            let (vars, exps) = unbound(vars, exps, &let_vars);
+            ConstituentExpression::Let(Let {
+                bindings,
+                body: substitute(&letexp.body, &vars, &exps),
             })
         }
-        ConstituentExpression::Let(_) => {
-            unreachable!("let expressions don't exist at this point")
+        ConstituentExpression::LetValues(letexp) => {
+            let let_vars: Vec<_> = letexp
+                .bindings
+                .iter()
+                .flat_map(|(formals, _)| formals.iter().map(|vd| vd.0.clone()))
+                .collect();
+            let bindings = letexp
+                .bindings
+                .iter()
+                .map(|(formals, val)| (formals.clone(), Box::from(substitute_one(val, vars, exps))))
+                .collect();
+            let (vars, exps) = unbound(vars, exps, &let_vars);
+            ConstituentExpression::LetValues(LetValues {
+                bindings,
+                body: substitute(&letexp.body, &vars, &exps),
+            })
+        }
+        ConstituentExpression::Delay(delay) => ConstituentExpression::Delay(Delay {
+            expr: Box::from(substitute_one(&delay.expr, vars, exps)),
+            lazy: delay.lazy,
+        }),
+        ConstituentExpression::Guard(guard) => {
+            let body = substitute(&guard.body, vars, exps);
+            let (vars, exps) = unbound(vars, exps, std::slice::from_ref(&guard.var.0));
+            ConstituentExpression::Guard(Guard {
+                var: guard.var.clone(),
+                clauses: guard
+                    .clauses
+                    .iter()
+                    .map(|clause| substitute_clause(clause, &vars, &exps))
+                    .collect(),
+                body,
+            })
         }
         _ => cexp.clone(),
     }
 }
 
+fn substitute_clause(
+    clause: &CondClause,
+    vars: &[String],
+    exps: &[ConstituentExpression],
+) -> CondClause {
+    CondClause {
+        test: clause
+            .test
+            .as_ref()
+            .map(|test| Box::from(substitute_one(test, vars, exps))),
+        body: substitute(&clause.body, vars, exps),
+        arrow: clause.arrow,
+    }
+}
+
+/// Drops the substitutions for variables that are rebound by an inner binder.
+fn unbound(
+    vars: &[String],
+    exps: &[ConstituentExpression],
+    bound: &[String],
+) -> (Vec<String>, Vec<ConstituentExpression>) {
+    vars.iter()
+        .zip(exps.iter())
+        .filter(|(v, _)| !bound.contains(v))
+        .map(|(v, e)| (v.clone(), e.clone()))
+        .unzip()
+}
+
+pub(crate) fn substitute_clauses(
+    clauses: &[CondClause],
+    vars: &[String],
+    exps: &[ConstituentExpression],
+) -> Vec<CondClause> {
+    clauses
+        .iter()
+        .map(|clause| substitute_clause(clause, vars, exps))
+        .collect()
+}
+
 pub(crate) fn substitute(
-    body: &[Box<ConstituentExpression>],
+    body: &[ConstituentExpression],
     vars: &[String],
     exps: &[ConstituentExpression],
 ) -> Vec<ConstituentExpression> {
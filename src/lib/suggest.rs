@@ -0,0 +1,76 @@
+// Spelling suggestions for names that are not bound.
+
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The candidates close enough to `name` to be what was meant, closest
+/// first. A third of the name's length in edits is allowed, and at least one,
+/// but not so many that nothing of the name is left.
+pub(crate) fn similar_names<'a>(
+    name: &str,
+    candidates: impl Iterator<Item = &'a str>,
+) -> Vec<String> {
+    let length = name.chars().count();
+    let max_distance = (length / 3).max(1).min(length.saturating_sub(1));
+    let mut close: Vec<(usize, &str)> = candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    close.sort_unstable();
+    close.dedup();
+    close
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_owned())
+        .collect()
+}
+
+// The number of insertions, deletions, substitutions and swaps of adjacent
+// characters that turn `a` into `b`, so that `lenght` is one edit from
+// `length`.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Rows i - 2, i - 1 and i of the distance table.
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        cur[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cur[j] = cur[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swapped_letters_are_one_edit() {
+        assert_eq!(distance("lenght", "length"), 1);
+        assert_eq!(distance("sqrt-itr", "sqrt-iter"), 1);
+        assert_eq!(distance("car", "cdr"), 1);
+        assert_eq!(distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn suggestions_are_close_names_closest_first() {
+        let names = ["length", "lengths", "list", "lenght"];
+        assert_eq!(
+            similar_names("lenght", names.into_iter()),
+            ["length", "lengths"]
+        );
+        assert!(similar_names("x", ["y", "xy"].into_iter()).is_empty());
+        let many = ["ab1", "ab2", "ab3", "ab4"];
+        assert_eq!(similar_names("ab", many.into_iter()).len(), MAX_SUGGESTIONS);
+    }
+}
@@ -0,0 +1,48 @@
+use std::fmt;
+
+/// The reductions recorded when tracing the substitution model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    // An operand about to be evaluated.
+    Operand,
+    // A closure body whose inner binders were renamed apart.
+    Rename,
+    // A closure body with the arguments substituted for its parameters.
+    Substitute,
+    // The branch an `if` expression selected.
+    If,
+}
+
+/// One reduction step. `depth` is the nesting of the evaluation the step
+/// belongs to, and `expression` the unparsed expression it produced.
+#[derive(Debug, Clone)]
+pub struct TraceStep {
+    pub kind: StepKind,
+    pub depth: usize,
+    pub expression: String,
+}
+
+impl fmt::Display for StepKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StepKind::Operand => "operand",
+            StepKind::Rename => "rename",
+            StepKind::Substitute => "substitute",
+            StepKind::If => "if",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:indent$}{}: {}",
+            "",
+            self.kind,
+            self.expression,
+            indent = 2 * self.depth
+        )
+    }
+}
@@ -1,18 +1,263 @@
 use crate::ast::*;
+use crate::env::Environment;
+use crate::eval::Continuation;
+use crate::port::Port;
+use crate::shared::{Lock, SendSync, Shared};
+use crate::{Arity, SchemeError};
+use std::any::{self, Any};
+use std::cell::Cell;
+use std::fmt;
 
 #[derive(Debug, Clone)]
 pub enum Value {
     Number(f64),
     Boolean(bool),
+    String(String),
+    Char(char),
     PrimitiveOperation(PrimitiveOperation),
     Closure(Closure),
-    SExpression(Box<SExpression>),
+    HostFunction(HostFunction),
+    Foreign(Foreign),
+    SExpression(Shared<SExpression>),
+    Port(Port),
+    Environment(Shared<Environment>),
+    Continuation(Continuation),
+    Promise(Promise),
+    Error(Box<ErrorObject>),
+    // Zero or several values returned by `values`; a single value is never
+    // wrapped.
+    Values(Vec<Value>),
+    Eof,
+    Void,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Closure {
     pub params: Vec<VariableDeclaration>,
-    pub body: Vec<Box<ConstituentExpression>>,
+    pub body: Shared<[ConstituentExpression]>,
+    // The environment the closure was created in. Bodies are evaluated by
+    // substitution, so this is used to draw environment diagrams and to apply
+    // the closure from the host outside of any evaluation.
+    pub(crate) env: Shared<Environment>,
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("params", &self.params)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+/// A procedure implemented in Rust, registered with
+/// `Interpreter::register_fn` or `Interpreter::register_typed`.
+#[derive(Clone)]
+pub struct HostFunction {
+    name: Shared<str>,
+    arity: usize,
+    func: Shared<HostFn>,
+}
+
+#[cfg(not(feature = "sync"))]
+type HostFn = dyn Fn(&[Value]) -> Result<Value, SchemeError>;
+#[cfg(feature = "sync")]
+type HostFn = dyn Fn(&[Value]) -> Result<Value, SchemeError> + Send + Sync;
+
+impl HostFunction {
+    pub fn new(
+        name: &str,
+        arity: usize,
+        func: impl Fn(&[Value]) -> Result<Value, SchemeError> + SendSync + 'static,
+    ) -> HostFunction {
+        HostFunction {
+            name: Shared::from(name),
+            arity,
+            func: Shared::new(func),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    /// Calls the function, checking the number of arguments first. Argument
+    /// and type errors name the function.
+    pub fn call(&self, args: &[Value]) -> Result<Value, SchemeError> {
+        if args.len() != self.arity {
+            let e = SchemeError::arity(Arity::Exactly(self.arity), args.len());
+            return Err(e.in_procedure(&self.name));
+        }
+        (self.func)(args).map_err(|e| e.in_procedure(&self.name))
+    }
+}
+
+impl fmt::Debug for HostFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#<procedure {}>", self.name)
+    }
+}
+
+/// A Rust object, such as a database connection, that Scheme code can store
+/// and pass around but not look into. Clones share the object.
+#[derive(Clone)]
+pub struct Foreign {
+    object: Shared<AnyObject>,
+    type_name: Shared<str>,
+    display: Option<Shared<ForeignDisplay>>,
+    eq: Option<Shared<ForeignEq>>,
+}
+
+#[cfg(not(feature = "sync"))]
+type AnyObject = dyn Any;
+#[cfg(feature = "sync")]
+type AnyObject = dyn Any + Send + Sync;
+#[cfg(not(feature = "sync"))]
+type ForeignDisplay = dyn Fn(&dyn Any, &mut fmt::Formatter<'_>) -> fmt::Result;
+#[cfg(feature = "sync")]
+type ForeignDisplay = dyn Fn(&dyn Any, &mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync;
+#[cfg(not(feature = "sync"))]
+type ForeignEq = dyn Fn(&dyn Any, &dyn Any) -> bool;
+#[cfg(feature = "sync")]
+type ForeignEq = dyn Fn(&dyn Any, &dyn Any) -> bool + Send + Sync;
+
+impl Foreign {
+    /// Wraps `object`, named after its Rust type.
+    pub fn new<T: Any + SendSync>(object: T) -> Foreign {
+        Foreign {
+            object: Shared::new(object),
+            type_name: Shared::from(any::type_name::<T>()),
+            display: None,
+            eq: None,
+        }
+    }
+
+    /// Sets the type name shown in `#<foreign name>`.
+    pub fn with_type_name(mut self, name: &str) -> Foreign {
+        self.type_name = Shared::from(name);
+        self
+    }
+
+    /// Prints the object with `display` instead of `#<foreign name>`.
+    pub fn with_display<T: Any>(
+        mut self,
+        display: impl Fn(&T, &mut fmt::Formatter<'_>) -> fmt::Result + SendSync + 'static,
+    ) -> Foreign {
+        self.display = Some(Shared::new(move |object, f| match object.downcast_ref() {
+            Some(object) => display(object, f),
+            None => Ok(()),
+        }));
+        self
+    }
+
+    /// Makes `eq?` compare objects of type `T` with `eq` rather than by
+    /// identity.
+    pub fn with_eq<T: Any>(mut self, eq: impl Fn(&T, &T) -> bool + SendSync + 'static) -> Foreign {
+        self.eq = Some(Shared::new(move |x, y| {
+            match (x.downcast_ref(), y.downcast_ref()) {
+                (Some(x), Some(y)) => eq(x, y),
+                _ => false,
+            }
+        }));
+        self
+    }
+
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    pub fn is<T: Any>(&self) -> bool {
+        self.object.is::<T>()
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.object.downcast_ref()
+    }
+
+    /// A shared handle to the object, if it is a `T`.
+    pub fn downcast<T: Any + SendSync>(&self) -> Option<Shared<T>> {
+        self.object.clone().downcast().ok()
+    }
+
+    pub(crate) fn is_eq(&self, other: &Foreign) -> bool {
+        match &self.eq {
+            Some(eq) => eq(&*self.object, &*other.object),
+            None => Shared::ptr_eq(&self.object, &other.object),
+        }
+    }
+}
+
+impl fmt::Debug for Foreign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#<foreign {}>", self.type_name)
+    }
+}
+
+impl fmt::Display for Foreign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.display {
+            Some(display) => display(&*self.object, f),
+            None => write!(f, "#<foreign {}>", self.type_name),
+        }
+    }
+}
+
+/// An error object, as created by `error` or from a built-in error caught by
+/// an exception handler.
+#[derive(Debug, Clone)]
+pub struct ErrorObject {
+    pub message: String,
+    pub irritants: Vec<Value>,
+    pub(crate) origin: Option<Box<SchemeError>>,
+}
+
+/// A promise made by `delay`, `delay-force` or `make-promise`. Clones share
+/// the same state, so a promise is only ever forced once.
+#[derive(Clone)]
+pub struct Promise(Shared<Lock<Shared<Lock<PromiseState>>>>);
+
+#[derive(Debug, Clone)]
+pub(crate) enum PromiseState {
+    Done(Value),
+    // `lazy` is set for `delay-force`, whose expression yields another promise.
+    Delayed {
+        expr: ConstituentExpression,
+        lazy: bool,
+    },
+}
+
+impl Promise {
+    pub(crate) fn new(state: PromiseState) -> Promise {
+        Promise(Shared::new(Lock::new(Shared::new(Lock::new(state)))))
+    }
+
+    pub(crate) fn state(&self) -> PromiseState {
+        self.0.borrow().borrow().clone()
+    }
+
+    /// Makes `self` take over the state of `other`, which from then on shares
+    /// the state of `self`. This is how `delay-force` chains are forced
+    /// without growing.
+    pub(crate) fn update_from(&self, other: &Promise) {
+        let state = other.state();
+        *self.0.borrow().borrow_mut() = state;
+        let shared = self.0.borrow().clone();
+        *other.0.borrow_mut() = shared;
+    }
+
+    pub(crate) fn fulfill(&self, value: Value) {
+        *self.0.borrow().borrow_mut() = PromiseState::Done(value);
+    }
+}
+
+impl fmt::Debug for Promise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#<promise>")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -27,3 +272,230 @@ impl SExpression {
         matches!(self, SExpression::Nil)
     }
 }
+
+impl Value {
+    pub fn nil() -> Value {
+        Value::SExpression(Shared::new(SExpression::Nil))
+    }
+
+    pub fn symbol(name: &str) -> Value {
+        Value::SExpression(Shared::new(SExpression::Symbol(name.to_owned())))
+    }
+
+    pub fn cons(first: Value, rest: Value) -> Value {
+        CONS_CELLS.with(|count| count.set(count.get() + 1));
+        Value::SExpression(Shared::new(SExpression::Compound(first, rest)))
+    }
+
+    pub fn list(items: Vec<Value>) -> Value {
+        Value::improper_list(items, Value::nil())
+    }
+
+    pub fn improper_list(items: Vec<Value>, tail: Value) -> Value {
+        items
+            .into_iter()
+            .rev()
+            .fold(tail, |acc, cur| Value::cons(cur, acc))
+    }
+
+    /// The elements of a proper list, or `None` if the value is not one.
+    pub fn list_items(&self) -> Option<Vec<Value>> {
+        let mut items = Vec::new();
+        let mut cur = self;
+        loop {
+            match cur {
+                Value::SExpression(sexpr) => match &**sexpr {
+                    SExpression::Nil => return Some(items),
+                    SExpression::Compound(first, rest) => {
+                        items.push(first.clone());
+                        cur = rest;
+                    }
+                    SExpression::Symbol(_) => return None,
+                },
+                _ => return None,
+            }
+        }
+    }
+
+    pub fn values(mut values: Vec<Value>) -> Value {
+        if values.len() == 1 {
+            values.pop().unwrap()
+        } else {
+            Value::Values(values)
+        }
+    }
+
+    /// The values a continuation received, as passed to a consumer.
+    pub fn into_values(self) -> Vec<Value> {
+        match self {
+            Value::Values(values) => values,
+            value => vec![value],
+        }
+    }
+
+    /// The foreign object in the value, if it is a `T`.
+    pub fn as_foreign<T: Any>(&self) -> Option<&T> {
+        match self {
+            Value::Foreign(foreign) => foreign.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn as_symbol(&self) -> Option<&str> {
+        match self {
+            Value::SExpression(sexpr) => match &**sexpr {
+                SExpression::Symbol(s) => Some(s),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns a wrapper that formats the value the way `display` prints it,
+    /// i.e. strings without quotes. `Value`'s own `Display` impl follows `write`.
+    pub fn display(&self) -> Displayed<'_> {
+        Displayed(self)
+    }
+}
+
+thread_local! {
+    static CONS_CELLS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The number of cons cells allocated so far on this thread.
+pub(crate) fn cons_cells_allocated() -> usize {
+    CONS_CELLS.with(Cell::get)
+}
+
+pub struct Displayed<'a>(&'a Value);
+
+impl fmt::Display for Displayed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_value(self.0, f, false)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_value(self, f, true)
+    }
+}
+
+impl fmt::Display for SExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_sexpr(self, f, true)
+    }
+}
+
+fn fmt_value(value: &Value, f: &mut fmt::Formatter<'_>, write: bool) -> fmt::Result {
+    match value {
+        Value::Number(n) => fmt_number(*n, f),
+        Value::Boolean(true) => write!(f, "#t"),
+        Value::Boolean(false) => write!(f, "#f"),
+        Value::String(s) if write => fmt_string_literal(s, f),
+        Value::String(s) => write!(f, "{}", s),
+        Value::Char(c) if write => fmt_char_literal(*c, f),
+        Value::Char(c) => write!(f, "{}", c),
+        Value::PrimitiveOperation(primop) => write!(f, "#<procedure {}>", primop.name()),
+        Value::Closure(_) => write!(f, "#<procedure>"),
+        Value::HostFunction(func) => write!(f, "#<procedure {}>", func.name()),
+        Value::Foreign(foreign) => write!(f, "{}", foreign),
+        Value::SExpression(sexpr) => fmt_sexpr(sexpr, f, write),
+        Value::Port(port) if port.is_input() => write!(f, "#<input-port>"),
+        Value::Port(_) => write!(f, "#<output-port>"),
+        Value::Environment(_) => write!(f, "#<environment>"),
+        Value::Continuation(_) => write!(f, "#<continuation>"),
+        Value::Promise(_) => write!(f, "#<promise>"),
+        Value::Error(err) => {
+            write!(f, "#<error ")?;
+            fmt_value(&Value::String(err.message.clone()), f, write)?;
+            for irritant in &err.irritants {
+                write!(f, " ")?;
+                fmt_value(irritant, f, write)?;
+            }
+            write!(f, ">")
+        }
+        Value::Values(values) => {
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                fmt_value(value, f, write)?;
+            }
+            Ok(())
+        }
+        Value::Eof => write!(f, "#<eof>"),
+        Value::Void => write!(f, "#<void>"),
+    }
+}
+
+fn fmt_number(n: f64, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if n.is_nan() {
+        write!(f, "+nan.0")
+    } else if n.is_infinite() {
+        write!(f, "{}inf.0", if n > 0.0 { "+" } else { "-" })
+    } else {
+        write!(f, "{}", n)
+    }
+}
+
+fn fmt_string_literal(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            _ => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn fmt_char_literal(c: char, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match c {
+        ' ' => write!(f, "#\\space"),
+        '\n' => write!(f, "#\\newline"),
+        '\t' => write!(f, "#\\tab"),
+        '\r' => write!(f, "#\\return"),
+        '\0' => write!(f, "#\\null"),
+        _ => write!(f, "#\\{}", c),
+    }
+}
+
+fn fmt_sexpr(sexpr: &SExpression, f: &mut fmt::Formatter<'_>, write: bool) -> fmt::Result {
+    match sexpr {
+        SExpression::Nil => write!(f, "()"),
+        SExpression::Symbol(s) => write!(f, "{}", s),
+        SExpression::Compound(first, rest) => {
+            write!(f, "(")?;
+            fmt_value(first, f, write)?;
+            let mut rest = rest;
+            loop {
+                match rest {
+                    Value::SExpression(sexpr) => match &**sexpr {
+                        SExpression::Nil => break,
+                        SExpression::Compound(first, next) => {
+                            write!(f, " ")?;
+                            fmt_value(first, f, write)?;
+                            rest = next;
+                        }
+                        SExpression::Symbol(_) => {
+                            write!(f, " . ")?;
+                            fmt_sexpr(sexpr, f, write)?;
+                            break;
+                        }
+                    },
+                    _ => {
+                        write!(f, " . ")?;
+                        fmt_value(rest, f, write)?;
+                        break;
+                    }
+                }
+            }
+            write!(f, ")")
+        }
+    }
+}
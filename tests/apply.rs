@@ -0,0 +1,151 @@
+use rusty_scheme::value::Value;
+use rusty_scheme::{EvalLimits, Interpreter, SchemeError};
+use std::cmp::Ordering;
+
+// `call-it` applies a thunk and `sort` sorts a list with a Scheme comparator.
+fn interpreter() -> Interpreter {
+    let mut interp = Interpreter::new();
+    interp.register_fn("call-it", 1, |args| Interpreter::apply(&args[0], &[]));
+    interp.register_fn("sort", 2, |args| {
+        let mut items = args[0].list_items().unwrap_or_default();
+        let mut error = None;
+        items.sort_by(
+            |a, b| match Interpreter::apply(&args[1], &[a.clone(), b.clone()]) {
+                Ok(Value::Boolean(true)) => Ordering::Less,
+                Ok(_) => Ordering::Greater,
+                Err(e) => {
+                    error.get_or_insert(e);
+                    Ordering::Equal
+                }
+            },
+        );
+        match error {
+            Some(e) => Err(e),
+            None => Ok(Value::list(items)),
+        }
+    });
+    interp
+}
+
+fn eval(src: &str) -> String {
+    interpreter().eval_str(src).unwrap().to_string()
+}
+
+fn error(src: &str) -> SchemeError {
+    interpreter().eval_str(src).unwrap_err()
+}
+
+#[test]
+fn host_functions_apply_comparators() {
+    assert_eq!(eval("(sort '(3 1 2) <)"), "(1 2 3)");
+    assert_eq!(eval("(sort '(3 1 2) (lambda (a b) (> a b)))"), "(3 2 1)");
+    assert_eq!(
+        eval(
+            "(define (by-distance a b) (< (abs a) (abs b)))
+              (define (abs x) (if (< x 0) (- x) x))
+              (sort '(-3 1 -2) by-distance)"
+        ),
+        "(1 -2 -3)"
+    );
+}
+
+#[test]
+fn applications_nest() {
+    assert_eq!(
+        eval("(call-it (lambda () (+ 1 (call-it (lambda () 41)))))"),
+        "42"
+    );
+}
+
+#[test]
+fn errors_propagate_through_scheme_and_rust_frames() {
+    let err = error("(call-it (lambda () (car 1)))");
+    assert_eq!(err.code(), "type-mismatch");
+    let frames: Vec<_> = err.backtrace().iter().map(|f| f.to_string()).collect();
+    assert_eq!(
+        frames,
+        ["(car 1)", "(#<procedure>)", "(call-it #<procedure>)"]
+    );
+    assert_eq!(
+        eval("(guard (e (#t (list 'caught e))) (call-it (lambda () (raise 'inner))))"),
+        "(caught inner)"
+    );
+    assert_eq!(
+        eval("(guard (e (#t 'caught)) (sort '(1 2) (lambda (a b) (car a))))"),
+        "caught"
+    );
+}
+
+#[test]
+fn continuations_cannot_escape_a_host_function() {
+    let err = error("(call/cc (lambda (k) (call-it (lambda () (k 1)))))");
+    assert_eq!(err.code(), "continuation-escape");
+    // Captured inside a host function that has returned since.
+    let captured = "(define k (call-it (lambda () (call/cc (lambda (c) c)))))";
+    let err = error(&format!("{} (+ 100 (if (number? k) k (k 5)))", captured));
+    assert_eq!(err.code(), "continuation-escape");
+    let err = error(&format!("{} (call-it (lambda () (k 5)))", captured));
+    assert_eq!(err.code(), "continuation-escape");
+    assert_eq!(
+        eval("(call-it (lambda () (call/cc (lambda (k) (+ 1 (k 5))))))"),
+        "5"
+    );
+}
+
+#[test]
+fn nested_applications_share_the_limits() {
+    let mut interp = interpreter();
+    interp.set_limits(EvalLimits {
+        max_steps: Some(1000),
+        ..EvalLimits::default()
+    });
+    let err = interp
+        .eval_str("(define (loop) (loop)) (call-it loop)")
+        .unwrap_err();
+    assert_eq!(err.code(), "limit-exceeded");
+}
+
+#[test]
+fn outside_a_host_function_closures_run_with_pure_capabilities() {
+    let mut interp = interpreter();
+    interp
+        .eval_str("(define (scale x) (* x factor)) (define factor 10) (define (noisy) (display 1))")
+        .unwrap();
+    let scale = interp.get_global("scale").unwrap();
+    assert_eq!(
+        Interpreter::apply(&scale, &[Value::Number(4.0)])
+            .unwrap()
+            .to_string(),
+        "40"
+    );
+    interp
+        .eval_str("(define (fact n) (if (= n 0) 1 (* n (fact (- n 1)))))")
+        .unwrap();
+    let fact = interp.get_global("fact").unwrap();
+    assert_eq!(
+        Interpreter::apply(&fact, &[Value::Number(5.0)])
+            .unwrap()
+            .to_string(),
+        "120"
+    );
+    let adder = interp
+        .eval_str("(define (make-adder n) (lambda (x) (+ x n))) (make-adder factor)")
+        .unwrap();
+    assert_eq!(
+        Interpreter::apply(&adder, &[Value::Number(1.0)])
+            .unwrap()
+            .to_string(),
+        "11"
+    );
+    let noisy = interp.get_global("noisy").unwrap();
+    assert_eq!(
+        Interpreter::apply(&noisy, &[]).unwrap_err().code(),
+        "permission-denied"
+    );
+    assert_eq!(
+        Interpreter::apply(&Value::Number(1.0), &[])
+            .unwrap_err()
+            .code(),
+        "bad-procedure"
+    );
+}
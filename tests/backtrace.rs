@@ -0,0 +1,99 @@
+use rusty_scheme::backtrace::StackFrame;
+use rusty_scheme::{Interpreter, SchemeError};
+
+fn error(src: &str) -> SchemeError {
+    Interpreter::new().eval_str(src).unwrap_err()
+}
+
+fn rendered(src: &str, err: &SchemeError) -> Vec<String> {
+    err.backtrace()
+        .iter()
+        .map(|frame| {
+            let (line, col) = frame.span.unwrap().line_col(src);
+            format!("{} {}:{}", frame, line, col)
+        })
+        .collect()
+}
+
+#[test]
+fn the_backtrace_lists_active_applications_innermost_first() {
+    let src = "(define (first l) (car l))\n(define (go n) (+ 1 (first n)))\n(go 5)";
+    let err = error(src);
+    assert_eq!(err.to_string(), "car: expected a pair, got 5");
+    assert_eq!(
+        rendered(src, &err),
+        ["(car 5) 1:19", "(first 5) 2:21", "(go 5) 3:1"]
+    );
+    assert!(matches!(err.root(), SchemeError::TypeMismatch { .. }));
+}
+
+#[test]
+fn tail_calls_are_collapsed_into_one_frame() {
+    let src = "(define (count n) (if (= n 0) (car 0) (count (- n 1))))\n(count 10)";
+    let err = error(src);
+    assert_eq!(
+        rendered(src, &err),
+        ["(car 0) 1:31", "(count 0) [10 tail calls] 1:39"]
+    );
+    assert_eq!(err.backtrace()[1].tail_calls, 10);
+}
+
+#[test]
+fn long_arguments_are_abbreviated() {
+    let err = error("(define (f s) (car s)) (f \"a very long string argument that goes on\")");
+    let frame: &StackFrame = &err.backtrace()[1];
+    assert_eq!(frame.name.as_deref(), Some("f"));
+    assert_eq!(frame.args, ["\"a very long string argument t..."]);
+}
+
+#[test]
+fn anonymous_procedures_have_no_name() {
+    let err = error("((lambda (x) (car x)) 1)");
+    let names: Vec<_> = err.backtrace().iter().map(|f| f.name.clone()).collect();
+    assert_eq!(names, [Some("car".to_owned()), None]);
+    assert_eq!(err.backtrace()[1].to_string(), "(#<procedure> 1)");
+}
+
+#[test]
+fn a_caught_error_leaves_no_backtrace() {
+    assert!(Interpreter::new()
+        .eval_str("(define (f) (car 1)) (guard (e (#t 'ok)) (f))")
+        .is_ok());
+}
+
+#[test]
+fn spans_are_offsets_into_their_own_source() {
+    let src = "(define (first l) (car l))\n(first 5)";
+    let err = error(src);
+    let spans: Vec<_> = err
+        .backtrace()
+        .iter()
+        .map(|frame| &src[frame.span.unwrap().offsets(src)])
+        .collect();
+    assert_eq!(spans, ["(car l)", "(first 5)"]);
+    assert_eq!(
+        err.backtrace()[0].span.unwrap().source(),
+        err.backtrace()[1].span.unwrap().source()
+    );
+}
+
+#[test]
+fn frames_from_an_earlier_source_have_no_span() {
+    let mut interp = Interpreter::new();
+    interp.eval_str("(define (first l)\n  (car l))").unwrap();
+    let src = "(first 5)";
+    let err = interp.eval_str(src).unwrap_err();
+    let frames: Vec<_> = err
+        .backtrace()
+        .iter()
+        .map(|frame| (frame.to_string(), frame.span.map(|span| span.line_col(src))))
+        .collect();
+    assert_eq!(
+        frames,
+        [
+            ("(car 5)".to_owned(), None),
+            ("(first 5)".to_owned(), Some((1, 1)))
+        ]
+    );
+    assert_eq!(err.span().unwrap().offsets(src), 0..9);
+}
@@ -0,0 +1,74 @@
+use rusty_scheme::limits;
+use rusty_scheme::value::Value;
+use rusty_scheme::{CancellationToken, Interpreter};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn cancelling_stops_a_running_evaluation() {
+    let token = CancellationToken::new();
+    limits::set_cancellation_token(token.clone());
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        token.cancel();
+    });
+    let mut interp = Interpreter::new();
+    let result = interp.eval_str("(define (loop) (loop)) (loop)");
+    canceller.join().unwrap();
+    assert_eq!(result.unwrap_err().code(), "interrupted");
+}
+
+#[test]
+fn an_interrupt_clears_the_token() {
+    let token = CancellationToken::new();
+    limits::set_cancellation_token(token.clone());
+    let mut interp = Interpreter::new();
+    token.cancel();
+    assert_eq!(
+        interp.eval_str("(+ 1 2)").unwrap_err().code(),
+        "interrupted"
+    );
+    assert!(!token.is_cancelled());
+    assert_eq!(interp.eval_str("(+ 1 2)").unwrap().to_string(), "3");
+}
+
+#[test]
+fn reset_withdraws_a_pending_cancellation() {
+    let token = CancellationToken::new();
+    limits::set_cancellation_token(token.clone());
+    token.cancel();
+    token.reset();
+    let mut interp = Interpreter::new();
+    assert_eq!(interp.eval_str("(* 2 3)").unwrap().to_string(), "6");
+}
+
+#[test]
+fn the_environment_survives_an_interrupt() {
+    let token = CancellationToken::new();
+    limits::set_cancellation_token(token.clone());
+    let mut interp = Interpreter::new();
+    interp.eval_str("(define x 1)").unwrap();
+    token.cancel();
+    assert!(interp.eval_str("(define y 2)").is_err());
+    assert_eq!(interp.eval_str("x").unwrap().to_string(), "1");
+}
+
+#[test]
+fn a_host_function_cannot_swallow_an_interrupt() {
+    let token = CancellationToken::new();
+    limits::set_cancellation_token(token.clone());
+    let mut interp = Interpreter::new();
+    interp.register_fn("ignore-errors", 1, |args| {
+        Ok(Interpreter::apply(&args[0], &[]).unwrap_or(Value::Void))
+    });
+    let canceller = token.clone();
+    interp.register_fn("interrupt", 0, move |_| {
+        canceller.cancel();
+        Ok(Value::Void)
+    });
+    let err = interp
+        .eval_str("(ignore-errors (lambda () (interrupt) 1)) 'still-running")
+        .unwrap_err();
+    assert_eq!(err.code(), "interrupted");
+    assert!(!token.is_cancelled());
+}
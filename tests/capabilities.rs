@@ -0,0 +1,258 @@
+use rusty_scheme::capability::Capabilities;
+use rusty_scheme::port::Port;
+use rusty_scheme::value::Value;
+use rusty_scheme::{Interpreter, SchemeError};
+use std::fs;
+use std::path::PathBuf;
+
+fn run(capabilities: Capabilities, src: &str) -> Result<Value, SchemeError> {
+    let mut interp = Interpreter::with_capabilities(capabilities);
+    // Anything that slips through is caught here instead of on stdout.
+    interp.set_output_port(Port::output_string());
+    interp.eval_str(src)
+}
+
+fn assert_denied(capabilities: Capabilities, src: &str, procedure: &str) {
+    match run(capabilities, src) {
+        Err(e) => match e.root() {
+            SchemeError::PermissionDenied { procedure: denied } => {
+                assert_eq!(denied, procedure, "{}", src)
+            }
+            other => panic!("{}: expected a denial, got {}", src, other),
+        },
+        Ok(value) => panic!("{}: expected a denial, got {}", src, value),
+    }
+}
+
+fn assert_allowed(capabilities: Capabilities, src: &str, expected: &str) {
+    match run(capabilities, src) {
+        Ok(value) => assert_eq!(value.to_string(), expected, "{}", src),
+        Err(e) => panic!("{}: expected {}, got {}", src, expected, e),
+    }
+}
+
+fn restricted() -> [Capabilities; 3] {
+    [
+        Capabilities::pure(),
+        Capabilities::no_io(),
+        Capabilities::read_only_fs(std::env::temp_dir()),
+    ]
+}
+
+#[test]
+fn the_console_is_denied_without_the_console_capability() {
+    for caps in restricted() {
+        assert_denied(caps.clone(), "(display 1)", "display");
+        assert_denied(caps.clone(), "(write \"s\")", "write");
+        assert_denied(caps.clone(), "(write-string \"s\")", "write-string");
+        assert_denied(caps.clone(), "(write-char #\\a)", "write-char");
+        assert_denied(caps.clone(), "(newline)", "newline");
+        assert_denied(caps.clone(), "(read-char)", "read-char");
+        assert_denied(caps.clone(), "(peek-char)", "peek-char");
+        assert_denied(caps.clone(), "(read-line)", "read-line");
+        assert_denied(caps.clone(), "(read-string 3)", "read-string");
+        assert_denied(caps.clone(), "(read)", "read");
+        assert_denied(caps.clone(), "(current-output-port)", "current-output-port");
+        assert_denied(caps.clone(), "(current-input-port)", "current-input-port");
+        assert_denied(caps, "(current-error-port)", "current-error-port");
+    }
+}
+
+#[test]
+fn a_port_as_the_datum_does_not_reach_the_console() {
+    let no_io = Capabilities::no_io;
+    assert_denied(no_io(), "(display (open-input-string \"x\"))", "display");
+    assert_denied(no_io(), "(write (open-output-string))", "write");
+    assert_denied(
+        no_io(),
+        "(define p (open-input-string \"(1)\")) (display p)",
+        "display",
+    );
+}
+
+#[test]
+fn string_ports_need_the_ports_capability() {
+    assert_denied(
+        Capabilities::pure(),
+        "(open-input-string \"x\")",
+        "open-input-string",
+    );
+    assert_denied(
+        Capabilities::pure(),
+        "(open-output-string)",
+        "open-output-string",
+    );
+    let src = "(define p (open-output-string))
+               (display 1 p) (write \"a\" p) (write-char #\\b p) (newline p)
+               (get-output-string p)";
+    assert_allowed(Capabilities::no_io(), src, "\"1\\\"a\\\"b\\n\"");
+    let src = "(define p (open-input-string \"ab\ncd (1 2)\"))
+               (list (peek-char p) (read-char p) (read-line p) (read-string 2 p) (read p))";
+    assert_allowed(Capabilities::no_io(), src, "(#\\a #\\a \"b\" \"cd\" (1 2))");
+}
+
+#[test]
+fn eval_runs_under_the_same_capabilities() {
+    for caps in restricted() {
+        assert_denied(
+            caps.clone(),
+            "(eval '(display 1) (interaction-environment))",
+            "display",
+        );
+        assert_denied(
+            caps.clone(),
+            "(eval '(eval '(current-output-port) (interaction-environment)) (interaction-environment))",
+            "current-output-port",
+        );
+        assert_denied(
+            caps,
+            "(eval '(open-output-file \"/tmp/out\") (scheme-report-environment 7))",
+            "open-output-file",
+        );
+    }
+}
+
+#[test]
+fn procedures_applied_by_host_functions_keep_the_capabilities() {
+    for caps in restricted() {
+        let mut interp = Interpreter::with_capabilities(caps);
+        interp.register_fn("call-it", 1, |args| Interpreter::apply(&args[0], &[]));
+        let err = interp
+            .eval_str("(call-it (lambda () (display 1)))")
+            .unwrap_err();
+        assert_eq!(err.code(), "permission-denied");
+    }
+}
+
+#[test]
+fn primitives_a_profile_lacks_are_not_installed() {
+    assert_denied(Capabilities::pure(), "(define d display) 1", "display");
+    assert_denied(
+        Capabilities::no_io(),
+        "(list car current-output-port)",
+        "current-output-port",
+    );
+    assert_denied(
+        Capabilities::no_io(),
+        "(eval 'open-output-file (scheme-report-environment 7))",
+        "open-output-file",
+    );
+    assert_allowed(Capabilities::no_io(), "(define d display) 1", "1");
+    assert_allowed(
+        Capabilities::pure(),
+        "(define (f) (open-output-file \"/tmp/out\")) (if #f (f) 'unused)",
+        "unused",
+    );
+    match run(Capabilities::pure(), "current-output-prt")
+        .unwrap_err()
+        .root()
+    {
+        SchemeError::UndefinedVariable { suggestions, .. } => assert!(suggestions.is_empty()),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn a_denial_can_be_caught() {
+    assert_allowed(
+        Capabilities::pure(),
+        "(guard (e (#t 'denied)) (display 1))",
+        "denied",
+    );
+}
+
+#[test]
+fn writing_files_needs_the_write_capability() {
+    for caps in restricted() {
+        assert_denied(
+            caps.clone(),
+            "(open-output-file \"/tmp/out\")",
+            "open-output-file",
+        );
+        assert_denied(
+            caps,
+            "(with-output-to-file \"/tmp/out\" (lambda () 1))",
+            "with-output-to-file",
+        );
+    }
+}
+
+// A directory with a readable file and a sibling outside of it.
+fn sandbox(name: &str) -> (PathBuf, PathBuf) {
+    let base = std::env::temp_dir().join(format!("rusty-scheme-{}-{}", name, std::process::id()));
+    let root = base.join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("inside.txt"), "inside\n").unwrap();
+    fs::write(base.join("secret.txt"), "secret\n").unwrap();
+    (base, root)
+}
+
+#[test]
+fn reading_files_is_confined_to_the_root() {
+    let (base, root) = sandbox("read");
+    let caps = || Capabilities::read_only_fs(&root);
+    let inside = root.join("inside.txt");
+    let src = format!("(read-line (open-input-file {:?}))", inside);
+    assert_allowed(caps(), &src, "\"inside\"");
+    let outside = base.join("secret.txt");
+    let src = format!("(open-input-file {:?})", outside);
+    assert_denied(caps(), &src, "open-input-file");
+    let escape = root.join("..").join("secret.txt");
+    let src = format!("(open-input-file {:?})", escape);
+    assert_denied(caps(), &src, "open-input-file");
+    let src = format!(
+        "(eval '(open-input-file {:?}) (interaction-environment))",
+        outside
+    );
+    assert_denied(caps(), &src, "open-input-file");
+    assert_denied(
+        Capabilities::no_io(),
+        &format!("(open-input-file {:?})", inside),
+        "open-input-file",
+    );
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn symbolic_links_out_of_the_root_are_denied() {
+    let (base, root) = sandbox("link");
+    let link = root.join("link.txt");
+    std::os::unix::fs::symlink(base.join("secret.txt"), &link).unwrap();
+    let src = format!("(open-input-file {:?})", link);
+    assert_denied(Capabilities::read_only_fs(&root), &src, "open-input-file");
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn symbolic_links_within_the_root_are_followed() {
+    let (base, root) = sandbox("inner-link");
+    let link = root.join("link.txt");
+    std::os::unix::fs::symlink(root.join("inside.txt"), &link).unwrap();
+    let src = format!("(read-line (open-input-file {:?}))", link);
+    assert_allowed(Capabilities::read_only_fs(&root), &src, "\"inside\"");
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn full_capabilities_allow_everything() {
+    assert_allowed(Capabilities::full(), "(display 1) (newline) 'ok", "ok");
+    assert_allowed(
+        Capabilities::full(),
+        "(eval '(display 1) (interaction-environment)) 'ok",
+        "ok",
+    );
+}
+
+#[test]
+fn host_functions_are_not_restricted() {
+    let mut interp = Interpreter::with_capabilities(Capabilities::pure());
+    interp.register_fn("host-io", 0, |_| {
+        Ok(Value::String("from the host".to_owned()))
+    });
+    assert_eq!(
+        interp.eval_str("(host-io)").unwrap().to_string(),
+        "\"from the host\""
+    );
+}
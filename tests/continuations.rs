@@ -0,0 +1,71 @@
+use rusty_scheme::Interpreter;
+
+fn eval(src: &str) -> String {
+    Interpreter::new().eval_str(src).unwrap().to_string()
+}
+
+#[test]
+fn escaping_continuations() {
+    assert_eq!(eval("(+ 1 (call/cc (lambda (k) (+ 10 (k 2)))))"), "3");
+    assert_eq!(eval("(call-with-current-continuation (lambda (k) 7))"), "7");
+    let src = "(define (search pred l return)
+                 (if (pair? l)
+                     (if (pred (car l)) (return (car l)) (search pred (cdr l) return))
+                     #f))
+               (define (find-first pred l)
+                 (call/cc (lambda (return) (search pred l return))))
+               (list (find-first (lambda (x) (< 2 x)) '(1 2 3 4))
+                     (find-first (lambda (x) (< 9 x)) '(1 2)))";
+    assert_eq!(eval(src), "(3 #f)");
+}
+
+#[test]
+fn escaping_from_deep_recursion() {
+    let src = "(define (product l k)
+                 (if (pair? l)
+                     (if (= (car l) 0) (k 0) (* (car l) (product (cdr l) k)))
+                     1))
+               (call/cc (lambda (k) (product '(1 2 3 0 4) k)))";
+    assert_eq!(eval(src), "0");
+}
+
+#[test]
+fn re_entering_a_continuation() {
+    assert_eq!(
+        eval("(let ((x (call/cc (lambda (k) k)))) (if (number? x) x (x 5)))"),
+        "5"
+    );
+    assert_eq!(eval("((call/cc (lambda (k) k)) (lambda (x) 'hi))"), "hi");
+}
+
+#[test]
+fn re_entering_repeatedly_makes_a_loop() {
+    let src = "(define (count-to n)
+                 (let ((p (call/cc (lambda (k) (cons 0 k)))))
+                   (if (< (car p) n)
+                       ((cdr p) (cons (+ (car p) 1) (cdr p)))
+                       (car p))))
+               (count-to 1000)";
+    assert_eq!(eval(src), "1000");
+}
+
+#[test]
+fn continuations_are_first_class_values() {
+    let mut interp = Interpreter::new();
+    interp
+        .eval_str("(define r (call/cc (lambda (k) (list 1 k))))")
+        .unwrap();
+    assert_eq!(interp.eval_str("(car r)").unwrap().to_string(), "1");
+    // The continuation of an earlier top-level expression ends with it.
+    assert_eq!(
+        interp.eval_str("((car (cdr r)) 2)").unwrap().to_string(),
+        "2"
+    );
+    assert_eq!(interp.eval_str("(car r)").unwrap().to_string(), "1");
+}
+
+#[test]
+fn call_cc_needs_a_procedure() {
+    let err = Interpreter::new().eval_str("(call/cc 5)").unwrap_err();
+    assert_eq!(err.code(), "bad-procedure");
+}
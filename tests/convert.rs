@@ -0,0 +1,219 @@
+use rusty_scheme::convert::{FromValue, IntoValue};
+use rusty_scheme::value::Value;
+use rusty_scheme::Interpreter;
+use std::collections::HashMap;
+
+fn read(src: &str) -> Value {
+    Interpreter::new().eval_str(src).unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]
+struct Config {
+    port: u16,
+    hosts: Vec<String>,
+    verbose: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]
+enum Shape {
+    Circle(f64),
+    Rect { width: f64, height: f64 },
+    Empty,
+}
+
+// Field names that the generated code could use itself.
+#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]
+struct Cart {
+    items: Vec<String>,
+    total: f64,
+    fields: u8,
+    value: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]
+enum Order {
+    Open { items: Vec<String>, tag: String },
+}
+
+#[test]
+fn fields_may_have_any_name() {
+    let cart = Cart {
+        items: vec!["apple".to_owned()],
+        total: 1.5,
+        fields: 2,
+        value: true,
+    };
+    let value = cart.clone().into_value();
+    assert_eq!(
+        value.to_string(),
+        "((items \"apple\") (total . 1.5) (fields . 2) (value . #t))"
+    );
+    assert_eq!(Cart::from_value(&value).unwrap(), cart);
+    let order = Order::Open {
+        items: vec![],
+        tag: "x".to_owned(),
+    };
+    let value = order.clone().into_value();
+    assert_eq!(value.to_string(), "(Open (items) (tag . \"x\"))");
+    assert_eq!(Order::from_value(&value).unwrap(), order);
+}
+
+#[test]
+fn structs_read_from_alists() {
+    let config = Config::from_value(&read(r#"'((port . 8080) (hosts "a" "b"))"#)).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            port: 8080,
+            hosts: vec!["a".to_owned(), "b".to_owned()],
+            verbose: None,
+        }
+    );
+}
+
+#[test]
+fn none_fields_are_left_out() {
+    let config = Config {
+        port: 1,
+        hosts: vec![],
+        verbose: None,
+    };
+    assert_eq!(config.into_value().to_string(), "((port . 1) (hosts))");
+}
+
+#[test]
+fn some_false_fields_round_trip() {
+    for verbose in [None, Some(false), Some(true)] {
+        let config = Config {
+            port: 1,
+            hosts: vec![],
+            verbose,
+        };
+        let value = config.clone().into_value();
+        assert_eq!(Config::from_value(&value).unwrap(), config);
+    }
+}
+
+#[test]
+fn a_bare_false_reads_as_some_false_for_option_bool() {
+    assert_eq!(
+        Option::<bool>::from_value(&Value::Boolean(false)).unwrap(),
+        Some(false)
+    );
+    assert_eq!(
+        Option::<String>::from_value(&Value::Boolean(false)).unwrap(),
+        None
+    );
+    assert!(Option::<u8>::from_value(&Value::String("x".to_owned())).is_err());
+}
+
+#[test]
+fn enums_are_tagged_lists() {
+    for shape in [
+        Shape::Circle(1.5),
+        Shape::Rect {
+            width: 2.0,
+            height: 3.0,
+        },
+        Shape::Empty,
+    ] {
+        let value = shape.clone().into_value();
+        assert_eq!(Shape::from_value(&value).unwrap(), shape);
+    }
+    assert_eq!(Shape::Circle(1.0).into_value().to_string(), "(Circle 1)");
+    assert_eq!(Shape::Empty.into_value().to_string(), "Empty");
+}
+
+#[test]
+fn integers_must_be_whole_and_in_range() {
+    assert_eq!(u8::from_value(&Value::Number(255.0)).unwrap(), 255);
+    assert!(u8::from_value(&Value::Number(256.0)).is_err());
+    assert!(u8::from_value(&Value::Number(-1.0)).is_err());
+    assert!(i32::from_value(&Value::Number(1.5)).is_err());
+    assert_eq!(i8::from_value(&Value::Number(-128.0)).unwrap(), -128);
+    assert!(i8::from_value(&Value::Number(128.0)).is_err());
+}
+
+#[test]
+fn the_top_of_the_64_bit_range_is_exclusive() {
+    assert!(u64::from_value(&Value::Number(18446744073709551616.0)).is_err());
+    assert!(i64::from_value(&Value::Number(9223372036854775808.0)).is_err());
+    assert_eq!(
+        i64::from_value(&Value::Number(-9223372036854775808.0)).unwrap(),
+        i64::MIN
+    );
+    assert_eq!(
+        u64::from_value(&Value::Number(9007199254740992.0)).unwrap(),
+        9007199254740992
+    );
+}
+
+#[test]
+fn non_finite_numbers_are_not_integers() {
+    for n in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert!(i64::from_value(&Value::Number(n)).is_err());
+        assert!(u64::from_value(&Value::Number(n)).is_err());
+    }
+}
+
+#[test]
+fn errors_name_the_failing_element_and_field() {
+    let err = Config::from_value(&read(r#"'((port . 8080) (hosts "a" 2))"#)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "field hosts: element 1: expected a string, got 2"
+    );
+    let err = Config::from_value(&read(r#"'((hosts))"#)).unwrap_err();
+    assert_eq!(err.code(), "type-mismatch");
+}
+
+#[test]
+fn maps_are_association_lists() {
+    let map = HashMap::<String, f64>::from_value(&read("'((a . 1) (b . 2))")).unwrap();
+    assert_eq!(map["a"], 1.0);
+    assert_eq!(map["b"], 2.0);
+    let map: HashMap<String, f64> = [("x".to_owned(), 3.0)].into_iter().collect();
+    assert_eq!(map.into_value().to_string(), "((\"x\" . 3))");
+}
+
+#[test]
+fn tuples_are_lists_of_the_same_length() {
+    let pair = <(f64, String)>::from_value(&read(r#"'(1 "a")"#)).unwrap();
+    assert_eq!(pair, (1.0, "a".to_owned()));
+    assert!(<(f64, String)>::from_value(&read("'(1)")).is_err());
+}
+
+#[test]
+fn scalars_convert_to_and_from_values() {
+    assert!(bool::from_value(&read("#t")).unwrap());
+    assert_eq!(String::from_value(&read("\"s\"")).unwrap(), "s");
+    assert_eq!(i32::from_value(&read("-7")).unwrap(), -7);
+    assert_eq!(
+        bool::from_value(&read("1")).unwrap_err().to_string(),
+        "expected a boolean, got 1"
+    );
+    assert_eq!(2.5f64.into_value().to_string(), "2.5");
+    assert_eq!("s".to_owned().into_value().to_string(), "\"s\"");
+    assert_eq!(vec![1u8, 2].into_value().to_string(), "(1 2)");
+}
+
+#[test]
+fn results_are_values_or_error_objects() {
+    let ok: Result<f64, String> = Ok(1.0);
+    assert_eq!(ok.into_value().to_string(), "1");
+    let err: Result<f64, String> = Err("failed".to_owned());
+    let value = err.into_value();
+    assert_eq!(
+        Result::<f64, String>::from_value(&value).unwrap(),
+        Err("failed".to_owned())
+    );
+    let caught = read("(guard (e (#t e)) (error \"boom\"))");
+    assert_eq!(
+        Result::<f64, String>::from_value(&caught).unwrap(),
+        Err("boom".to_owned())
+    );
+    assert_eq!(
+        Result::<f64, String>::from_value(&read("3")).unwrap(),
+        Ok(3.0)
+    );
+}
@@ -0,0 +1,187 @@
+use rusty_scheme::diagram::EnvironmentDiagram;
+use rusty_scheme::{eval_program_with_diagrams, parse_program, EvaluationOrder};
+
+fn diagrams(src: &str) -> Vec<EnvironmentDiagram> {
+    diagrams_in_order(src, EvaluationOrder::Applicative)
+}
+
+fn diagrams_in_order(src: &str, order: EvaluationOrder) -> Vec<EnvironmentDiagram> {
+    let (_, program) = parse_program(src).unwrap();
+    let mut snapshots = vec![];
+    eval_program_with_diagrams(&program, order, &mut |diagram| {
+        snapshots.push(diagram.clone())
+    })
+    .unwrap();
+    snapshots
+}
+
+fn values(diagram: &EnvironmentDiagram, frame: usize) -> Vec<&str> {
+    diagram.frames[frame]
+        .bindings
+        .iter()
+        .map(|binding| binding.value.as_str())
+        .collect()
+}
+
+fn names(diagram: &EnvironmentDiagram, frame: usize) -> Vec<&str> {
+    diagram.frames[frame]
+        .bindings
+        .iter()
+        .map(|binding| binding.name.as_str())
+        .collect()
+}
+
+#[test]
+fn snapshots_are_taken_at_breakpoints_and_the_end() {
+    let snapshots = diagrams("(L3 (define a 1) (breakpoint 'first) (define b 2) (breakpoint) b)");
+    let labels: Vec<_> = snapshots.iter().map(|d| d.label.as_deref()).collect();
+    assert_eq!(labels, [Some("first"), None, Some("end")]);
+    assert_eq!(names(&snapshots[0], 0), ["a"]);
+    assert_eq!(names(&snapshots[1], 0), ["a", "b"]);
+    assert_eq!(snapshots[0].frames[0].name, "GE");
+}
+
+#[test]
+fn closures_point_to_the_frame_they_were_made_in() {
+    let snapshots = diagrams(
+        "(L3 (define (make-adder n) (lambda (x) (+ x n)))
+             (define add1 (make-adder 1))
+             (define other (eval '(lambda (y) y) (scheme-report-environment 7)))
+             'done)",
+    );
+    let end = snapshots.last().unwrap();
+    assert_eq!(names(end, 0), ["make-adder", "add1", "other"]);
+    assert_eq!(end.frames.len(), 2);
+    assert_eq!(end.frames[1].name, "E1");
+    let closure = |name: &str| {
+        let binding = end.frames[0]
+            .bindings
+            .iter()
+            .find(|binding| binding.name == name)
+            .unwrap();
+        &end.closures[binding.closure.unwrap()]
+    };
+    assert_eq!(closure("make-adder").params, ["n"]);
+    assert_eq!(closure("make-adder").body, "(lambda (x) (+ x n))");
+    assert_eq!(closure("make-adder").parent, 0);
+    assert_eq!(closure("add1").body, "(+ x__1 1)");
+    assert_eq!(closure("other").parent, 1);
+}
+
+#[test]
+fn procedure_calls_in_progress_have_frames() {
+    let src = "(L3 (define (square x) (breakpoint 'inside) (* x x))
+                   (define (sum-of-squares a b) (+ (square a) (square b)))
+                   (sum-of-squares 3 4))";
+    let snapshots = diagrams(src);
+    let first = &snapshots[0];
+    assert_eq!(first.frames.len(), 3);
+    assert_eq!(first.frames[0].parent, None);
+    assert_eq!(first.frames[1].name, "E1");
+    assert_eq!(names(first, 1), ["a", "b"]);
+    assert_eq!(values(first, 1), ["3", "4"]);
+    assert_eq!(first.frames[1].parent, Some(0));
+    assert_eq!(names(first, 2), ["x"]);
+    assert_eq!(values(first, 2), ["3"]);
+    assert_eq!(first.frames[2].parent, Some(0));
+    assert_eq!(values(&snapshots[1], 2), ["4"]);
+    assert_eq!(snapshots.last().unwrap().frames.len(), 1);
+}
+
+#[test]
+fn a_tail_call_replaces_the_frame_of_its_caller() {
+    let src = "(L3 (define (count n) (if (= n 0) (breakpoint) (count (- n 1))))
+                   (count 3))";
+    let snapshots = diagrams(src);
+    assert_eq!(snapshots[0].frames.len(), 2);
+    assert_eq!(values(&snapshots[0], 1), ["0"]);
+}
+
+#[test]
+fn arguments_that_are_closures_point_to_them() {
+    let snapshots = diagrams("(L3 (define (call f) (list (f))) (call (lambda () (breakpoint))))");
+    let binding = &snapshots[0].frames[1].bindings[0];
+    assert_eq!(binding.name, "f");
+    let closure = &snapshots[0].closures[binding.closure.unwrap()];
+    assert_eq!(closure.body, "(breakpoint)");
+    assert_eq!(closure.parent, 0);
+}
+
+#[test]
+fn in_normal_order_parameters_are_bound_to_the_operands() {
+    let snapshots = diagrams_in_order(
+        "(L3 (define (f x) (breakpoint) x) (f (+ 1 2)))",
+        EvaluationOrder::Normal,
+    );
+    assert_eq!(values(&snapshots[0], 1), ["(+ 1 2)"]);
+}
+
+#[test]
+fn a_redefinition_replaces_the_binding() {
+    let snapshots = diagrams("(L3 (define x 1) (define y 2) (define x 3) 'done)");
+    let end = snapshots.last().unwrap();
+    assert_eq!(names(end, 0), ["x", "y"]);
+    assert_eq!(end.frames[0].bindings[0].value, "3");
+    assert_eq!(end.frames[0].bindings[0].closure, None);
+}
+
+#[test]
+fn ascii_diagrams_draw_frames_and_closures() {
+    let snapshots = diagrams("(L3 (define n 10) (define (f x) x) 'done)");
+    assert_eq!(
+        snapshots[0].to_ascii(),
+        "== end ==
++----------------+
+| GE             |
++----------------+
+| n: 10          |
+| f: -> closure0 |
++----------------+
+closure0: params (x), body x, parent GE
+"
+    );
+}
+
+#[test]
+fn dot_diagrams_link_bindings_closures_and_parents() {
+    let snapshots = diagrams("(L3 (define n 10) (define (f x) x) 'done)");
+    assert_eq!(
+        snapshots[0].to_dot(),
+        "digraph environment {
+    node [shape=record];
+    label=\"end\";
+    frame0 [label=\"{GE|<b0> n: 10\\l|<b1> f: \\l}\"];
+    frame0:b1 -> closure0;
+    closure0 [shape=box, style=rounded, label=\"p: x\\lb: x\\l\"];
+    closure0 -> frame0 [style=dashed];
+}
+"
+    );
+}
+
+#[test]
+fn call_frames_are_drawn_with_their_parent() {
+    let snapshots = diagrams("(L3 (define (f x) (breakpoint) x) (f 1))");
+    assert_eq!(
+        snapshots[0].to_ascii(),
+        "+----------------+
+| GE             |
++----------------+
+| f: -> closure0 |
++----------------+
++----------------+
+| E1 (parent GE) |
++----------------+
+| x: 1           |
++----------------+
+closure0: params (x), body (breakpoint) x, parent GE
+"
+    );
+    assert!(snapshots[0].to_dot().contains("frame1 -> frame0;\n"));
+}
+
+#[test]
+fn dot_labels_are_escaped() {
+    let snapshots = diagrams("(L3 (define s \"a|b\") 'done)");
+    assert!(snapshots[0].to_dot().contains("s: \\\"a\\|b\\\"\\l"));
+}
@@ -0,0 +1,88 @@
+use rusty_scheme::port::Port;
+use rusty_scheme::{Interpreter, SchemeError};
+
+// Evaluates `src` and returns its value (or error code) and what it printed.
+fn run(src: &str) -> (Result<String, &'static str>, String) {
+    let mut interp = Interpreter::new();
+    let port = Port::output_string();
+    interp.set_output_port(port.clone());
+    interp
+        .eval_str(
+            "(define (in) (display \"[in]\"))
+             (define (out) (display \"[out]\"))",
+        )
+        .unwrap();
+    let result = interp
+        .eval_str(src)
+        .map(|v| v.to_string())
+        .map_err(|e: SchemeError| e.code());
+    (result, port.output_string_contents().unwrap())
+}
+
+#[test]
+fn before_and_after_surround_the_thunk() {
+    let (result, output) = run("(dynamic-wind in (lambda () (display \"body\") 'value) out)");
+    assert_eq!(result, Ok("value".to_owned()));
+    assert_eq!(output, "[in]body[out]");
+}
+
+#[test]
+fn escaping_runs_the_after_thunk() {
+    let (result, output) = run("(+ 1 (call/cc (lambda (k)
+                 (dynamic-wind in (lambda () (k 41) (display \"unreached\")) out))))");
+    assert_eq!(result, Ok("42".to_owned()));
+    assert_eq!(output, "[in][out]");
+}
+
+#[test]
+fn re_entering_runs_the_before_thunk_again() {
+    let (result, output) = run(
+        "(let ((p (dynamic-wind in (lambda () (call/cc (lambda (k) (cons 0 k)))) out)))
+           (if (< (car p) 2) ((cdr p) (cons (+ (car p) 1) (cdr p))) (car p)))",
+    );
+    assert_eq!(result, Ok("2".to_owned()));
+    assert_eq!(output, "[in][out][in][out][in][out]");
+}
+
+#[test]
+fn nested_extents_unwind_innermost_first() {
+    let (result, output) = run("(call/cc (lambda (k)
+           (dynamic-wind
+             (lambda () (display \"[a\"))
+             (lambda () (dynamic-wind
+                          (lambda () (display \"[b\"))
+                          (lambda () (k 'done))
+                          (lambda () (display \"b]\"))))
+             (lambda () (display \"a]\")))))");
+    assert_eq!(result, Ok("done".to_owned()));
+    assert_eq!(output, "[a[bb]a]");
+}
+
+#[test]
+fn a_caught_error_runs_the_after_thunk_first() {
+    let (result, output) = run("(guard (e (#t (display \"[caught]\") e))
+           (dynamic-wind in (lambda () (raise 'oops)) out))");
+    assert_eq!(result, Ok("oops".to_owned()));
+    assert_eq!(output, "[in][out][caught]");
+}
+
+#[test]
+fn an_uncaught_error_runs_the_after_thunk() {
+    let (result, output) = run("(dynamic-wind in (lambda () (car 5)) out)");
+    assert_eq!(result, Err("type-mismatch"));
+    assert_eq!(output, "[in][out]");
+}
+
+#[test]
+fn escaping_restores_the_current_output_port() {
+    let path = std::env::temp_dir().join(format!("rusty-scheme-wind-{}", std::process::id()));
+    let (result, output) = run(&format!(
+        "(call/cc (lambda (k) (with-output-to-file {:?} (lambda () (display \"file\") (k 0)))))
+         (display \"console\")",
+        path
+    ));
+    assert!(result.is_ok());
+    assert_eq!(output, "console");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "file");
+    std::fs::remove_file(path).unwrap();
+}
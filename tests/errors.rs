@@ -0,0 +1,156 @@
+use rusty_scheme::value::Value;
+use rusty_scheme::{Arity, EvalLimits, Interpreter, SchemeError};
+use std::error::Error;
+
+fn error(src: &str) -> SchemeError {
+    Interpreter::new().eval_str(src).unwrap_err()
+}
+
+#[test]
+fn errors_have_stable_codes() {
+    let cases = [
+        ("(car 1", "parse-error"),
+        ("undefined-thing", "undefined-variable"),
+        ("(1 2)", "bad-procedure"),
+        ("(car 1)", "type-mismatch"),
+        ("((lambda (x) x))", "argument-mismatch"),
+        ("(let-values (((a b) (values 1))) a)", "values-mismatch"),
+        ("(open-input-file \"/nonexistent/file.scm\")", "io-error"),
+        ("(read (open-input-string \"(1\"))", "read-error"),
+        (
+            "(eval '(define x 1) (interaction-environment))",
+            "syntax-error",
+        ),
+        ("(raise 'oops)", "raise"),
+        ("(error \"bad thing\" 1)", "raise"),
+    ];
+    for (src, code) in cases {
+        assert_eq!(error(src).code(), code, "{}", src);
+    }
+}
+
+#[test]
+fn messages_are_written_for_end_users() {
+    let cases = [
+        ("undefined-thing", "Undefined variable: undefined-thing"),
+        ("(1 2)", "1 is not a procedure"),
+        ("(car 1)", "car: expected a pair, got 1"),
+        ("(+ 'a 1)", "+: expected a number, got a"),
+        ("(car 1 2)", "car: expected 1 argument, got 2"),
+        ("((lambda (x) x))", "expected 1 argument, got 0"),
+        (
+            "(let-values (((a b) (values 1))) a)",
+            "Expected 2 values, got 1",
+        ),
+        ("(raise 'oops)", "Uncaught exception: oops"),
+        ("(error \"bad thing\" 1 \"two\")", "bad thing 1 \"two\""),
+    ];
+    for (src, message) in cases {
+        assert_eq!(error(src).to_string(), message, "{}", src);
+    }
+}
+
+#[test]
+fn variants_carry_structured_details() {
+    match error("(car 1 2)").root() {
+        SchemeError::ArgumentMismatch {
+            procedure,
+            expected,
+            actual,
+        } => {
+            assert_eq!(procedure.as_deref(), Some("car"));
+            assert_eq!(*expected, Arity::Exactly(1));
+            assert_eq!(*actual, 2);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match error("(car \"s\")").root() {
+        SchemeError::TypeMismatch {
+            procedure,
+            expected,
+            actual,
+            ..
+        } => {
+            assert_eq!(procedure.as_deref(), Some("car"));
+            assert_eq!(expected, "a pair");
+            assert_eq!(actual.to_string(), "\"s\"");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(matches!(
+        error("(raise 42)").root(),
+        SchemeError::Raise(Value::Number(n)) if *n == 42.0
+    ));
+}
+
+#[test]
+fn limit_errors_name_the_limit() {
+    let mut interp = Interpreter::new();
+    interp.set_limits(EvalLimits {
+        max_steps: Some(100),
+        ..EvalLimits::default()
+    });
+    let err = interp
+        .eval_str("(define (loop) (loop)) (loop)")
+        .unwrap_err();
+    assert_eq!(err.code(), "limit-exceeded");
+    assert!(err.to_string().starts_with("Limit exceeded: "), "{}", err);
+}
+
+#[test]
+fn io_errors_keep_their_source() {
+    let err = error("(open-input-file \"/nonexistent/file.scm\")");
+    assert_eq!(err.to_string(), "Cannot open /nonexistent/file.scm");
+    let source = err.source().expect("an io error source");
+    let io = source.downcast_ref::<std::io::Error>().unwrap();
+    assert_eq!(io.kind(), std::io::ErrorKind::NotFound);
+    assert_eq!(
+        err.to_string_with_sources(),
+        format!("Cannot open /nonexistent/file.scm: {}", io)
+    );
+    assert!(error("(car 1)").source().is_none());
+}
+
+#[test]
+fn errors_work_with_the_question_mark_operator() {
+    fn run(src: &str) -> Result<Value, Box<dyn Error>> {
+        Ok(Interpreter::new().eval_str(src)?)
+    }
+    assert_eq!(run("(+ 1 2)").unwrap().to_string(), "3");
+    assert_eq!(
+        run("(car '())").unwrap_err().to_string(),
+        "car: expected a pair, got ()"
+    );
+}
+
+#[test]
+fn parse_errors_point_at_the_offending_character() {
+    let src = "(define x 1)\n(+ x 1))";
+    let err = error(src);
+    assert_eq!(err.to_string(), "Parse error: unexpected character ')'");
+    assert_eq!(err.span().unwrap().line_col(src), (2, 8));
+    match err.root() {
+        SchemeError::ParseError(e) => assert_eq!(e.found, Some(')')),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(err.backtrace().is_empty());
+}
+
+#[test]
+fn parse_errors_at_the_end_of_the_input() {
+    let src = "(define (f x)\n  (car x)";
+    let err = error(src);
+    assert_eq!(err.to_string(), "Parse error: unexpected end of input");
+    assert_eq!(err.span().unwrap().line_col(src), (2, 10));
+    match err.root() {
+        SchemeError::ParseError(e) => assert_eq!(e.found, None),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn runtime_errors_are_located_by_their_innermost_application() {
+    let src = "(define (f x)\n  (car x))\n(f 1)";
+    assert_eq!(error(src).span().unwrap().line_col(src), (2, 3));
+    assert!(error("undefined-thing").span().is_none());
+}
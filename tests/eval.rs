@@ -0,0 +1,104 @@
+use rusty_scheme::Interpreter;
+
+fn eval(interp: &mut Interpreter, src: &str) -> String {
+    interp.eval_str(src).unwrap().to_string()
+}
+
+fn error_code(src: &str) -> &'static str {
+    Interpreter::new().eval_str(src).unwrap_err().code()
+}
+
+#[test]
+fn eval_runs_a_constructed_datum() {
+    let mut interp = Interpreter::new();
+    assert_eq!(
+        eval(
+            &mut interp,
+            "(eval (list '+ 1 2) (interaction-environment))"
+        ),
+        "3"
+    );
+    assert_eq!(
+        eval(
+            &mut interp,
+            "(eval '((lambda (x) (if (< x 0) (- x) x)) -4) (scheme-report-environment 7))"
+        ),
+        "4"
+    );
+    assert_eq!(eval(&mut interp, "(eval 5 (interaction-environment))"), "5");
+}
+
+#[test]
+fn the_interaction_environment_sees_definitions() {
+    let mut interp = Interpreter::new();
+    interp.eval_str("(define x 10)").unwrap();
+    assert_eq!(
+        eval(&mut interp, "(eval '(* x 2) (interaction-environment))"),
+        "20"
+    );
+    assert_eq!(
+        eval(
+            &mut interp,
+            "(define (f y) (eval '(+ x 1) (interaction-environment))) (f 0)"
+        ),
+        "11"
+    );
+}
+
+#[test]
+fn the_report_environment_has_only_standard_bindings() {
+    assert_eq!(
+        error_code("(define x 10) (eval 'x (scheme-report-environment 7))"),
+        "undefined-variable"
+    );
+    assert_eq!(
+        eval(
+            &mut Interpreter::new(),
+            "(eval '(car '(1 2)) (scheme-report-environment 7))"
+        ),
+        "1"
+    );
+}
+
+// Primitive procedures are syntax in L3, so they are available everywhere.
+#[test]
+fn the_null_environment_has_no_definitions() {
+    assert_eq!(
+        eval(
+            &mut Interpreter::new(),
+            "(eval '(if #t (car '(yes)) 'no) (null-environment 7))"
+        ),
+        "yes"
+    );
+    assert_eq!(
+        error_code("(define x 1) (eval 'x (null-environment 7))"),
+        "undefined-variable"
+    );
+}
+
+#[test]
+fn environments_are_first_class() {
+    let mut interp = Interpreter::new();
+    interp
+        .eval_str("(define env (scheme-report-environment 7))")
+        .unwrap();
+    assert_eq!(eval(&mut interp, "(eval '(+ 1 1) env)"), "2");
+}
+
+#[test]
+fn only_version_7_is_supported() {
+    assert_eq!(error_code("(scheme-report-environment 5)"), "type-mismatch");
+}
+
+#[test]
+fn eval_rejects_malformed_data_and_definitions() {
+    assert_eq!(
+        error_code("(eval '(if) (interaction-environment))"),
+        "syntax-error"
+    );
+    assert_eq!(
+        error_code("(eval '(define y 3) (interaction-environment))"),
+        "syntax-error"
+    );
+    assert_eq!(error_code("(eval 1 2)"), "type-mismatch");
+}
@@ -0,0 +1,109 @@
+use rusty_scheme::{Interpreter, SchemeError};
+
+fn eval(src: &str) -> String {
+    Interpreter::new().eval_str(src).unwrap().to_string()
+}
+
+fn error(src: &str) -> SchemeError {
+    Interpreter::new().eval_str(src).unwrap_err()
+}
+
+#[test]
+fn error_objects_carry_a_message_and_irritants() {
+    let src = "(guard (e ((error-object? e)
+                          (list (error-object-message e) (error-object-irritants e))))
+                 (error \"bad thing:\" 1 'two))";
+    assert_eq!(eval(src), "(\"bad thing:\" (1 two))");
+    assert_eq!(
+        eval("(guard (e (#t (error-object-irritants e))) (error \"no irritants\"))"),
+        "()"
+    );
+}
+
+#[test]
+fn guard_catches_raised_objects() {
+    assert_eq!(
+        eval("(guard (e ((symbol? e) (list 'caught e))) (raise 'boom))"),
+        "(caught boom)"
+    );
+    assert_eq!(
+        eval("(guard (e ((symbol? e) 'symbol) (else 'other)) (raise 42))"),
+        "other"
+    );
+    assert_eq!(
+        eval("(guard (e ((pair? e) => (lambda (p) (list 'pair p))) ((number? e))) (raise '(1)))"),
+        "(pair #t)"
+    );
+    assert_eq!(
+        eval("(guard (e ((pair? e) => (lambda (p) (list 'pair p))) ((number? e))) (raise 23))"),
+        "#t"
+    );
+}
+
+#[test]
+fn guard_without_a_matching_clause_re_raises() {
+    assert_eq!(
+        eval(
+            "(guard (outer (#t (list 'outer outer)))
+                (guard (inner ((number? inner) 'inner))
+                  (raise 'sym)))"
+        ),
+        "(outer sym)"
+    );
+    let err = error("(guard (e ((number? e) 'inner)) (raise 'sym))");
+    assert_eq!(err.code(), "raise");
+}
+
+#[test]
+fn the_guard_body_value_is_returned_without_a_raise() {
+    assert_eq!(eval("(guard (e (#t 'caught)) (+ 1 2))"), "3");
+}
+
+#[test]
+fn built_in_errors_are_catchable_conditions() {
+    assert_eq!(
+        eval("(guard (e ((error-object? e) 'caught)) (car 5))"),
+        "caught"
+    );
+    assert_eq!(
+        eval("(guard (e ((error-object? e) 'caught)) undefined-name)"),
+        "caught"
+    );
+    assert_eq!(
+        eval("(guard (e ((error-object? e) 'caught)) ((lambda (x) x)))"),
+        "caught"
+    );
+}
+
+#[test]
+fn raise_continuable_returns_the_handler_value() {
+    assert_eq!(
+        eval(
+            "(with-exception-handler
+                (lambda (c) 42)
+                (lambda () (+ (raise-continuable 'oops) 1)))"
+        ),
+        "43"
+    );
+}
+
+#[test]
+fn returning_from_a_raise_handler_is_an_error() {
+    let src = "(with-exception-handler (lambda (c) 42) (lambda () (raise 'oops)))";
+    assert!(Interpreter::new().eval_str(src).is_err());
+}
+
+#[test]
+fn handlers_run_in_the_outer_handler_context() {
+    let src = "(guard (e (#t (list 'outer e)))
+                 (with-exception-handler
+                   (lambda (c) (raise (list 'wrapped c)))
+                   (lambda () (raise 'inner))))";
+    assert_eq!(eval(src), "(outer (wrapped inner))");
+}
+
+#[test]
+fn uncaught_errors_reach_the_host() {
+    let err = error("(error \"custom failure\" 7)");
+    assert!(err.to_string().contains("custom failure"), "{}", err);
+}
@@ -0,0 +1,126 @@
+use rusty_scheme::port::Port;
+use rusty_scheme::value::{Foreign, Value};
+use rusty_scheme::{Interpreter, SchemeError};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+struct Connection {
+    url: String,
+}
+
+// `open` makes a connection and `url` reads it back from a handle.
+fn interpreter() -> Interpreter {
+    let mut interp = Interpreter::new();
+    interp.register_fn("open", 1, |args| match &args[0] {
+        Value::String(url) => Ok(Value::Foreign(
+            Foreign::new(Connection { url: url.clone() }).with_type_name("connection"),
+        )),
+        other => Err(SchemeError::TypeMismatch {
+            procedure: None,
+            expected: "a string".to_owned(),
+            actual: Box::from(other.clone()),
+            path: vec![],
+        }),
+    });
+    interp.register_fn("url", 1, |args| match args[0].as_foreign::<Connection>() {
+        Some(connection) => Ok(Value::String(connection.url.clone())),
+        None => Err(SchemeError::TypeMismatch {
+            procedure: None,
+            expected: "a connection".to_owned(),
+            actual: Box::from(args[0].clone()),
+            path: vec![],
+        }),
+    });
+    interp
+}
+
+fn eval(interp: &mut Interpreter, src: &str) -> String {
+    interp.eval_str(src).unwrap().to_string()
+}
+
+#[test]
+fn scheme_code_stores_and_passes_foreign_objects() {
+    let mut interp = interpreter();
+    let src = "(define conns (list (open \"db://a\") (open \"db://b\")))
+               (list (url (car conns)) (url (car (cdr conns))))";
+    assert_eq!(eval(&mut interp, src), "(\"db://a\" \"db://b\")");
+    assert_eq!(
+        interp.eval_str("(url 5)").unwrap_err().to_string(),
+        "url: expected a connection, got 5"
+    );
+}
+
+#[test]
+fn foreign_predicate() {
+    let mut interp = interpreter();
+    assert_eq!(
+        eval(
+            &mut interp,
+            "(list (foreign? (open \"x\")) (foreign? 1) (foreign? '(1)))"
+        ),
+        "(#t #f #f)"
+    );
+}
+
+#[test]
+fn foreign_objects_print_their_type_name() {
+    let mut interp = interpreter();
+    assert_eq!(eval(&mut interp, "(open \"x\")"), "#<foreign connection>");
+    let value = Value::Foreign(Foreign::new(3u8));
+    assert_eq!(value.to_string(), "#<foreign u8>");
+}
+
+struct Point(i32, i32);
+
+fn point(x: i32, y: i32) -> Value {
+    Value::Foreign(
+        Foreign::new(Point(x, y))
+            .with_display(|p: &Point, f: &mut fmt::Formatter<'_>| {
+                write!(f, "#<point {} {}>", p.0, p.1)
+            })
+            .with_eq(|p: &Point, q: &Point| p.0 == q.0 && p.1 == q.1),
+    )
+}
+
+#[test]
+fn custom_display_is_used_by_display_and_write() {
+    let mut interp = Interpreter::new();
+    interp.define("p", point(1, 2));
+    let port = Port::output_string();
+    interp.set_output_port(port.clone());
+    interp.eval_str("(display p) (write p)").unwrap();
+    assert_eq!(
+        port.output_string_contents().unwrap(),
+        "#<point 1 2>#<point 1 2>"
+    );
+}
+
+#[test]
+fn eq_compares_identity_unless_customized() {
+    let mut interp = interpreter();
+    interp.define("p", point(1, 2));
+    interp.define("q", point(1, 2));
+    interp.define("r", point(2, 1));
+    assert_eq!(eval(&mut interp, "(list (eq? p q) (eq? p r))"), "(#t #f)");
+    assert_eq!(
+        eval(
+            &mut interp,
+            "(define c (open \"x\")) (list (eq? c c) (eq? c (open \"x\")))"
+        ),
+        "(#t #f)"
+    );
+}
+
+#[test]
+fn downcasting_checks_the_type() {
+    let foreign = Foreign::new(Connection {
+        url: "u".to_owned(),
+    });
+    assert!(foreign.is::<Connection>());
+    assert!(!foreign.is::<Point>());
+    assert!(foreign.downcast_ref::<Point>().is_none());
+    let shared = foreign.downcast::<Connection>().unwrap();
+    assert_eq!(shared.url, "u");
+    assert!(foreign.type_name().ends_with("Connection"));
+    assert!(Value::Number(1.0).as_foreign::<Connection>().is_none());
+}
@@ -0,0 +1,152 @@
+use rusty_scheme::limits;
+use rusty_scheme::value::Value;
+use rusty_scheme::{Arity, CancellationToken, EvalLimits, Interpreter, SchemeError};
+
+fn interpreter() -> Interpreter {
+    let mut interp = Interpreter::new();
+    interp.register_fn("sum", 2, |args| match args {
+        [Value::Number(a), Value::Number(b)] => Ok(Value::Number(a + b)),
+        // The procedure name is filled in when the error leaves the function.
+        [a, _] => Err(SchemeError::TypeMismatch {
+            procedure: None,
+            expected: "a number".to_owned(),
+            actual: Box::from(a.clone()),
+            path: vec![],
+        }),
+        _ => unreachable!("the arity is checked first"),
+    });
+    interp.register_typed::<(f64, String), bool>("longer?", |(n, s)| s.len() as f64 > n);
+    interp.register_typed::<(f64,), Result<f64, String>>("checked-sqrt", |(n,)| {
+        if n < 0.0 {
+            Err(format!("negative argument {}", n))
+        } else {
+            Ok(n.sqrt())
+        }
+    });
+    interp.register_typed::<(), Vec<String>>("names", |()| vec!["a".to_owned(), "b".to_owned()]);
+    interp
+}
+
+fn eval(src: &str) -> String {
+    interpreter().eval_str(src).unwrap().to_string()
+}
+
+fn error(src: &str) -> SchemeError {
+    interpreter().eval_str(src).unwrap_err()
+}
+
+#[test]
+fn host_functions_are_procedures() {
+    assert_eq!(eval("(sum 1 2)"), "3");
+    assert_eq!(eval("sum"), "#<procedure sum>");
+    assert_eq!(
+        eval("(define (twice f x) (f (f x 1) 1)) (twice sum 5)"),
+        "7"
+    );
+}
+
+#[test]
+fn typed_functions_convert_arguments_and_results() {
+    assert_eq!(eval("(longer? 2 \"abc\")"), "#t");
+    assert_eq!(eval("(longer? 5 \"abc\")"), "#f");
+    assert_eq!(eval("(checked-sqrt 16)"), "4");
+    assert_eq!(eval("(names)"), "(\"a\" \"b\")");
+}
+
+#[test]
+fn arity_errors_name_the_function() {
+    let err = error("(sum 1 2 3)");
+    match err.root() {
+        SchemeError::ArgumentMismatch {
+            procedure,
+            expected,
+            actual,
+        } => {
+            assert_eq!(procedure.as_deref(), Some("sum"));
+            assert_eq!(*expected, Arity::Exactly(2));
+            assert_eq!(*actual, 3);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(
+        error("(longer? 1)").to_string(),
+        "longer?: expected 2 arguments, got 1"
+    );
+}
+
+#[test]
+fn type_errors_name_the_function() {
+    let err = error("(longer? \"x\" \"abc\")");
+    assert_eq!(err.code(), "type-mismatch");
+    assert_eq!(err.to_string(), "longer?: expected a number, got \"x\"");
+    assert_eq!(
+        error("(sum 'a 1)").to_string(),
+        "sum: expected a number, got a"
+    );
+}
+
+#[test]
+fn errors_returned_by_typed_functions_are_raised() {
+    assert_eq!(error("(checked-sqrt -1)").code(), "raise");
+    assert_eq!(
+        eval("(guard (e ((error-object? e) (error-object-message e))) (checked-sqrt -1))"),
+        "\"negative argument -1\""
+    );
+}
+
+#[test]
+fn host_function_errors_are_catchable() {
+    assert_eq!(eval("(guard (e (#t 'caught)) (longer? 1 2))"), "caught");
+    assert_eq!(eval("(guard (e (#t 'caught)) (sum 1))"), "caught");
+}
+
+#[test]
+fn registering_again_replaces_the_function() {
+    let mut interp = interpreter();
+    interp.register_typed::<(f64, f64), f64>("sum", |(a, b)| a * b);
+    assert_eq!(interp.eval_str("(sum 3 4)").unwrap().to_string(), "12");
+}
+
+#[test]
+fn scheme_errors_returned_by_typed_functions_keep_their_kind() {
+    let mut interp = interpreter();
+    interp.register_typed::<(Value,), Result<Value, SchemeError>>("call", |(f,)| {
+        Interpreter::apply(&f, &[])
+    });
+    assert_eq!(
+        interp
+            .eval_str("(guard (e (#t (list 'caught e))) (call (lambda () (raise 'inner))))")
+            .unwrap()
+            .to_string(),
+        "(caught inner)"
+    );
+    interp.set_limits(EvalLimits {
+        max_depth: Some(50),
+        ..EvalLimits::default()
+    });
+    let err = interp
+        .eval_str(
+            "(define (deep n) (if (= n 0) 0 (+ 1 (deep (- n 1)))))
+             (guard (e (#t (list 'caught e))) (call (lambda () (deep 100))))",
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), "limit-exceeded");
+}
+
+#[test]
+fn interrupts_cannot_be_caught_through_typed_functions() {
+    let token = CancellationToken::new();
+    limits::set_cancellation_token(token.clone());
+    let mut interp = interpreter();
+    interp.register_typed::<(Value,), Result<Value, SchemeError>>("call", |(f,)| {
+        Interpreter::apply(&f, &[])
+    });
+    interp.register_typed::<(), bool>("interrupt", move |()| {
+        token.cancel();
+        true
+    });
+    let err = interp
+        .eval_str("(guard (e (#t 'caught)) (call (lambda () (interrupt) (interrupt))))")
+        .unwrap_err();
+    assert_eq!(err.code(), "interrupted");
+}
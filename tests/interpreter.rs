@@ -0,0 +1,112 @@
+use rusty_scheme::value::Value;
+use rusty_scheme::Interpreter;
+
+fn eval(interp: &mut Interpreter, src: &str) -> String {
+    interp.eval_str(src).unwrap().to_string()
+}
+
+#[test]
+fn definitions_persist_across_evaluations() {
+    let mut interp = Interpreter::new();
+    interp.eval_str("(define (square x) (* x x))").unwrap();
+    interp.eval_str("(define n 4)").unwrap();
+    assert_eq!(eval(&mut interp, "(square n)"), "16");
+}
+
+#[test]
+fn bare_expressions_and_l3_programs_both_parse() {
+    let mut interp = Interpreter::new();
+    assert_eq!(eval(&mut interp, "(define x 2) (+ x 1)"), "3");
+    assert_eq!(eval(&mut interp, "(L3 (define y 5) (* x y))"), "10");
+}
+
+#[test]
+fn define_replaces_the_previous_binding() {
+    let mut interp = Interpreter::new();
+    for i in 0..1000 {
+        interp.define("counter", Value::Number(i as f64));
+    }
+    assert_eq!(interp.get_global("counter").unwrap().to_string(), "999");
+    interp.eval_str("(define counter 'done)").unwrap();
+    assert_eq!(eval(&mut interp, "counter"), "done");
+}
+
+#[test]
+fn get_global_of_an_unbound_name_is_none() {
+    let interp = Interpreter::new();
+    assert!(interp.get_global("nothing").is_none());
+}
+
+#[test]
+fn call_applies_a_global_procedure() {
+    let mut interp = Interpreter::new();
+    interp.eval_str("(define (add a b) (+ a b))").unwrap();
+    let sum = interp
+        .call("add", &[Value::Number(1.0), Value::Number(2.0)])
+        .unwrap();
+    assert_eq!(sum.to_string(), "3");
+    let err = interp.call("addd", &[]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Undefined variable: addd (did you mean add?)"
+    );
+}
+
+#[test]
+fn an_error_does_not_lose_earlier_definitions() {
+    let mut interp = Interpreter::new();
+    assert!(interp.eval_str("(define a 1) (car 5)").is_err());
+    assert_eq!(eval(&mut interp, "a"), "1");
+}
+
+#[test]
+fn non_finite_numbers_read_back() {
+    let mut interp = Interpreter::new();
+    assert_eq!(
+        eval(&mut interp, "(list +inf.0 -inf.0 +nan.0)"),
+        "(+inf.0 -inf.0 +nan.0)"
+    );
+    assert_eq!(eval(&mut interp, "(< 1e308 +inf.0)"), "#t");
+    assert_eq!(eval(&mut interp, "(define +inf 3) +inf"), "3");
+}
+
+#[test]
+fn eval_file_loads_definitions() {
+    let path = std::env::temp_dir().join(format!("rusty-scheme-lib-{}.scm", std::process::id()));
+    std::fs::write(&path, "(define (double x) (* 2 x))\n(define base 21)\n").unwrap();
+    let mut interp = Interpreter::new();
+    interp.eval_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(eval(&mut interp, "(double base)"), "42");
+    let err = interp.eval_file(&path).unwrap_err();
+    assert_eq!(err.code(), "io-error");
+}
+
+#[test]
+fn eval_file_skips_comments_and_whitespace() {
+    let path =
+        std::env::temp_dir().join(format!("rusty-scheme-comments-{}.scm", std::process::id()));
+    let src = ";;; A library with comments.
+(define (square x) ; the argument
+  (* x x)          ; its square
+  )
+(define (sum-of-squares a b)
+  ;; Both squared.
+  (+ (square a)
+     (square b) ))
+(define (sign x)
+  (if (< x 0) ; negative?
+      -1
+      1 ))
+; trailing comment";
+    std::fs::write(&path, src).unwrap();
+    let mut interp = Interpreter::new();
+    interp.eval_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(eval(&mut interp, "(sum-of-squares 3 4) ; 25"), "25");
+    assert_eq!(eval(&mut interp, "(sign -2)"), "-1");
+    assert_eq!(
+        eval(&mut interp, "(let ((a 1) ; one\n (b 2) ) (list a b ))"),
+        "(1 2)"
+    );
+}
@@ -0,0 +1,117 @@
+use rusty_scheme::limits::Limit;
+use rusty_scheme::{
+    eval_program_with_limits, parse_program, EvalLimits, EvaluationOrder, Interpreter, SchemeError,
+};
+use std::time::{Duration, Instant};
+
+const LOOP: &str = "(define (loop) (loop)) (loop)";
+const DEEP: &str = "(define (down n) (if (= n 0) 0 (+ 1 (down (- n 1))))) (down 10000)";
+const BUILD: &str = "(define (build n) (if (= n 0) '() (cons n (build (- n 1))))) (build 1000)";
+
+fn run(src: &str, limits: EvalLimits) -> Result<String, SchemeError> {
+    let mut interp = Interpreter::new();
+    interp.set_limits(limits);
+    interp.eval_str(src).map(|value| value.to_string())
+}
+
+fn exceeded(src: &str, limits: EvalLimits) -> (Limit, SchemeError) {
+    let err = run(src, limits).unwrap_err();
+    match err.root() {
+        SchemeError::LimitExceeded(limit) => (*limit, err),
+        other => panic!("{}: expected a limit, got {}", src, other),
+    }
+}
+
+#[test]
+fn step_limit() {
+    let limits = EvalLimits {
+        max_steps: Some(1000),
+        ..EvalLimits::default()
+    };
+    let (limit, err) = exceeded(LOOP, limits.clone());
+    assert_eq!(limit, Limit::Steps(1000));
+    assert_eq!(err.code(), "limit-exceeded");
+    assert_eq!(
+        err.to_string(),
+        "Limit exceeded: more than 1000 evaluation steps"
+    );
+    assert_eq!(run("(+ 1 2)", limits).unwrap(), "3");
+}
+
+#[test]
+fn depth_limit_reports_a_partial_stack() {
+    let limits = EvalLimits {
+        max_depth: Some(100),
+        ..EvalLimits::default()
+    };
+    let (limit, err) = exceeded(DEEP, limits);
+    assert_eq!(limit, Limit::Depth(100));
+    assert!(!err.backtrace().is_empty());
+    assert!(err.backtrace().len() <= 100);
+    assert_eq!(err.backtrace()[0].name.as_deref(), Some("down"));
+}
+
+#[test]
+fn tail_calls_do_not_count_towards_the_depth() {
+    let limits = EvalLimits {
+        max_depth: Some(100),
+        ..EvalLimits::default()
+    };
+    let src = "(define (count n) (if (= n 0) 'done (count (- n 1)))) (count 10000)";
+    assert_eq!(run(src, limits).unwrap(), "done");
+}
+
+#[test]
+fn cons_cell_limit() {
+    let limits = EvalLimits {
+        max_cons_cells: Some(100),
+        ..EvalLimits::default()
+    };
+    assert_eq!(exceeded(BUILD, limits.clone()).0, Limit::ConsCells(100));
+    assert_eq!(run("(list 1 2 3)", limits).unwrap(), "(1 2 3)");
+}
+
+#[test]
+fn deadline() {
+    let limits = EvalLimits {
+        deadline: Some(Instant::now() + Duration::from_millis(50)),
+        ..EvalLimits::default()
+    };
+    assert_eq!(exceeded(LOOP, limits).0, Limit::Deadline);
+}
+
+#[test]
+fn limits_cannot_be_caught_by_scheme_code() {
+    let limits = EvalLimits {
+        max_steps: Some(1000),
+        ..EvalLimits::default()
+    };
+    let src = "(define (loop) (loop)) (guard (e (#t 'caught)) (loop))";
+    assert_eq!(exceeded(src, limits).0, Limit::Steps(1000));
+}
+
+#[test]
+fn each_evaluation_gets_the_full_budget() {
+    let mut interp = Interpreter::new();
+    interp.set_limits(EvalLimits {
+        max_cons_cells: Some(10),
+        ..EvalLimits::default()
+    });
+    for _ in 0..5 {
+        assert!(interp.eval_str("(list 1 2 3 4 5)").is_ok());
+    }
+}
+
+#[test]
+fn the_program_api_applies_limits() {
+    let (_, program) = parse_program(&format!("(L3 {})", LOOP)).unwrap();
+    let limits = EvalLimits {
+        max_steps: Some(500),
+        ..EvalLimits::default()
+    };
+    let err = eval_program_with_limits(&program, EvaluationOrder::Applicative, limits).unwrap_err();
+    assert!(matches!(
+        err.root(),
+        SchemeError::LimitExceeded(Limit::Steps(500))
+    ));
+}
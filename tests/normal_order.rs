@@ -0,0 +1,71 @@
+use rusty_scheme::port::Port;
+use rusty_scheme::{
+    eval_program_in_order, normal_eval_program, parse_program, EvalLimits, EvaluationOrder,
+    Interpreter,
+};
+
+fn interpreter(order: EvaluationOrder) -> Interpreter {
+    let mut interp = Interpreter::new();
+    interp.set_order(order);
+    interp.set_limits(EvalLimits {
+        max_steps: Some(100_000),
+        ..EvalLimits::default()
+    });
+    interp
+}
+
+const UNUSED_LOOP: &str = "(define (loop) (loop)) (define (f x y) x) (f 1 (loop))";
+
+#[test]
+fn unused_operands_are_never_evaluated() {
+    let mut interp = interpreter(EvaluationOrder::Normal);
+    assert_eq!(interp.eval_str(UNUSED_LOOP).unwrap().to_string(), "1");
+    let mut interp = interpreter(EvaluationOrder::Applicative);
+    assert_eq!(
+        interp.eval_str(UNUSED_LOOP).unwrap_err().code(),
+        "limit-exceeded"
+    );
+}
+
+#[test]
+fn the_program_api_selects_the_order() {
+    let (_, program) = parse_program("(L3 (define (f x y) x) (f 1 (car 5)))").unwrap();
+    assert_eq!(normal_eval_program(&program).unwrap().to_string(), "1");
+    assert!(eval_program_in_order(&program, EvaluationOrder::Applicative).is_err());
+}
+
+#[test]
+fn operands_are_evaluated_each_time_they_are_used() {
+    let src = "(define (noisy) (display \"*\") 2)
+               (define (twice x) (+ x x))
+               (twice (noisy))";
+    for (order, printed) in [
+        (EvaluationOrder::Normal, "**"),
+        (EvaluationOrder::Applicative, "*"),
+    ] {
+        let mut interp = interpreter(order);
+        let port = Port::output_string();
+        interp.set_output_port(port.clone());
+        assert_eq!(interp.eval_str(src).unwrap().to_string(), "4");
+        assert_eq!(port.output_string_contents().unwrap(), printed);
+    }
+}
+
+#[test]
+fn primitives_and_conditions_force_their_operands() {
+    let mut interp = interpreter(EvaluationOrder::Normal);
+    let src = "(define (choose c a b) (if c a b))
+               (define (loop) (loop))
+               (list (choose (< 1 2) (+ 1 1) (loop)) (choose #f (loop) (* 3 3)))";
+    assert_eq!(interp.eval_str(src).unwrap().to_string(), "(2 9)");
+}
+
+#[test]
+fn both_orders_agree_on_terminating_programs() {
+    let src = "(define (fact n) (if (= n 0) 1 (* n (fact (- n 1)))))
+               (define (compose f g) (lambda (x) (f (g x))))
+               ((compose fact (lambda (x) (+ x 1))) 4)";
+    for order in [EvaluationOrder::Normal, EvaluationOrder::Applicative] {
+        assert_eq!(interpreter(order).eval_str(src).unwrap().to_string(), "120");
+    }
+}
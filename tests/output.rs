@@ -0,0 +1,64 @@
+use rusty_scheme::port::Port;
+use rusty_scheme::value::Value;
+use rusty_scheme::Interpreter;
+
+// Evaluates `src` and returns what it printed.
+fn output(src: &str) -> String {
+    let mut interp = Interpreter::new();
+    let port = Port::output_string();
+    interp.set_output_port(port.clone());
+    interp.eval_str(src).unwrap();
+    port.output_string_contents().unwrap()
+}
+
+fn written(src: &str) -> String {
+    Interpreter::new().eval_str(src).unwrap().to_string()
+}
+
+#[test]
+fn values_print_as_external_representations() {
+    assert_eq!(written("'(1 2 . 3)"), "(1 2 . 3)");
+    assert_eq!(written("'(1 (2 3) ())"), "(1 (2 3) ())");
+    assert_eq!(written("(list #t #f)"), "(#t #f)");
+    assert_eq!(written("1.5"), "1.5");
+    assert_eq!(written("'sym"), "sym");
+    assert_eq!(written("\"a \\\"b\\\"\\n\""), "\"a \\\"b\\\"\\n\"");
+    assert_eq!(written("car"), "#<procedure car>");
+    assert!(written("(lambda (x) x)").starts_with("#<procedure"));
+}
+
+#[test]
+fn display_leaves_strings_unquoted() {
+    assert_eq!(output("(display \"a b\")"), "a b");
+    assert_eq!(output("(display '(1 \"x\" #t))"), "(1 x #t)");
+    let s = Value::String("x".to_owned());
+    assert_eq!(s.display().to_string(), "x");
+    assert_eq!(s.to_string(), "\"x\"");
+}
+
+#[test]
+fn write_quotes_strings() {
+    assert_eq!(output("(write \"a b\")"), "\"a b\"");
+    assert_eq!(output("(write '(1 \"x\"))"), "(1 \"x\")");
+}
+
+#[test]
+fn write_string_and_newline() {
+    assert_eq!(
+        output("(write-string \"one\") (newline) (display 2) (newline)"),
+        "one\n2\n"
+    );
+}
+
+#[test]
+fn output_goes_to_the_configured_port() {
+    let mut interp = Interpreter::new();
+    let first = Port::output_string();
+    interp.set_output_port(first.clone());
+    interp.eval_str("(display 1)").unwrap();
+    let second = Port::output_string();
+    interp.set_output_port(second.clone());
+    interp.eval_str("(display 2)").unwrap();
+    assert_eq!(first.output_string_contents().unwrap(), "1");
+    assert_eq!(second.output_string_contents().unwrap(), "2");
+}
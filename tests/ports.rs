@@ -0,0 +1,138 @@
+use rusty_scheme::port::Port;
+use rusty_scheme::Interpreter;
+use std::fs;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+fn eval(src: &str) -> String {
+    Interpreter::new().eval_str(src).unwrap().to_string()
+}
+
+#[test]
+fn string_input_ports() {
+    let src = "(define p (open-input-string \"ab\nline two\nxyz\"))
+               (list (peek-char p) (read-char p) (read-char p) (read-char p)
+                     (read-line p) (read-string 2 p) (read-string 5 p)
+                     (eof-object? (read-char p)) (eof-object? (peek-char p)))";
+    assert_eq!(
+        eval(src),
+        "(#\\a #\\a #\\b #\\newline \"line two\" \"xy\" \"z\" #t #t)"
+    );
+}
+
+#[test]
+fn eof_objects() {
+    assert_eq!(eval("(eof-object? (eof-object))"), "#t");
+    assert_eq!(eval("(eof-object? \"\")"), "#f");
+    assert_eq!(
+        eval("(eof-object? (read-line (open-input-string \"\")))"),
+        "#t"
+    );
+}
+
+#[test]
+fn characters_read_from_ports_can_be_compared() {
+    let src = "(define (count-char c p)
+                 (if (eof-object? (peek-char p))
+                     0
+                     (+ (if (eq? (read-char p) c) 1 0) (count-char c p))))
+               (count-char #\\a (open-input-string \"banana\"))";
+    assert_eq!(eval(src), "3");
+    assert_eq!(
+        eval("(list (eq? #\\a #\\a) (eq? #\\a #\\b) (eq? #\\a \"a\"))"),
+        "(#t #f #f)"
+    );
+    assert_eq!(
+        eval("(eq? (read-char (open-input-string \"\")) (eof-object))"),
+        "#t"
+    );
+}
+
+#[test]
+fn string_output_ports() {
+    let src = "(define p (open-output-string))
+               (write-char #\\a p) (write-string \"bc\" p) (display 1 p)
+               (get-output-string p)";
+    assert_eq!(eval(src), "\"abc1\"");
+    assert_eq!(
+        eval("(call-with-output-string (lambda (p) (write 'x p) (write \"y\" p)))"),
+        "\"x\\\"y\\\"\""
+    );
+}
+
+#[test]
+fn closed_ports_cannot_be_used() {
+    let mut interp = Interpreter::new();
+    interp
+        .eval_str("(define p (open-input-string \"abc\")) (close-port p)")
+        .unwrap();
+    assert!(interp.eval_str("(read-char p)").is_err());
+}
+
+#[test]
+fn current_ports_can_be_passed_explicitly() {
+    let mut interp = Interpreter::new();
+    let out = Port::output_string();
+    interp.set_input_port(Port::input_string("xy"));
+    interp.set_output_port(out.clone());
+    interp
+        .eval_str("(write-char (read-char (current-input-port)) (current-output-port))")
+        .unwrap();
+    assert_eq!(out.output_string_contents().unwrap(), "x");
+}
+
+#[test]
+fn file_ports() {
+    let path = std::env::temp_dir().join(format!("rusty-scheme-ports-{}", std::process::id()));
+    let src = format!(
+        "(define out (open-output-file {path:?}))
+         (display \"first line\" out) (newline out) (write '(1 2) out)
+         (close-port out)
+         (define in (open-input-file {path:?}))
+         (list (read-line in) (read-line in) (eof-object? (read-line in)))",
+        path = path
+    );
+    assert_eq!(eval(&src), "(\"first line\" \"(1 2)\" #t)");
+    let src = format!(
+        "(with-output-to-file {:?} (lambda () (display \"redirected\")))",
+        path
+    );
+    eval(&src);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "redirected");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn missing_files_raise_catchable_errors() {
+    assert_eq!(
+        eval("(guard (e (#t 'missing)) (open-input-file \"/nonexistent/file\"))"),
+        "missing"
+    );
+}
+
+#[derive(Clone, Default)]
+struct Sink(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn embedders_can_redirect_the_standard_ports() {
+    let mut interp = Interpreter::new();
+    let sink = Sink::default();
+    interp.set_input_port(Port::from_reader(io::Cursor::new("from the host\n")));
+    interp.set_output_port(Port::from_writer(sink.clone()));
+    interp.set_error_port(Port::from_writer(sink.clone()));
+    interp
+        .eval_str("(display (read-line)) (write-string \"!\" (current-error-port))")
+        .unwrap();
+    let written = String::from_utf8(sink.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(written, "from the host!");
+}
@@ -0,0 +1,74 @@
+use rusty_scheme::port::Port;
+use rusty_scheme::Interpreter;
+
+fn eval(src: &str) -> String {
+    Interpreter::new().eval_str(src).unwrap().to_string()
+}
+
+#[test]
+fn force_evaluates_a_delayed_expression_once() {
+    let mut interp = Interpreter::new();
+    let port = Port::output_string();
+    interp.set_output_port(port.clone());
+    let value = interp
+        .eval_str(
+            "(define p (delay (begin-work)))
+             (define (begin-work) (display \"working \") 42)
+             (list (force p) (force p))",
+        )
+        .unwrap();
+    assert_eq!(value.to_string(), "(42 42)");
+    assert_eq!(port.output_string_contents().unwrap(), "working ");
+}
+
+#[test]
+fn a_promise_sees_later_definitions() {
+    assert_eq!(eval("(define p (delay (g))) (define (g) 1) (force p)"), "1");
+}
+
+#[test]
+fn delay_does_not_evaluate_its_expression() {
+    assert_eq!(eval("(define p (delay (car 5))) (promise? p)"), "#t");
+}
+
+#[test]
+fn make_promise_and_promise_predicate() {
+    assert_eq!(eval("(force (make-promise 7))"), "7");
+    assert_eq!(eval("(promise? (make-promise 7))"), "#t");
+    assert_eq!(eval("(promise? (make-promise (make-promise 7)))"), "#t");
+    assert_eq!(eval("(force (make-promise (delay 8)))"), "8");
+    assert_eq!(eval("(promise? 7)"), "#f");
+    assert_eq!(eval("(force 7)"), "7");
+}
+
+#[test]
+fn delay_force_runs_in_constant_space() {
+    let src = "(define (loop n) (delay-force (if (= n 0) (delay 'done) (loop (- n 1)))))
+               (force (loop 100000))";
+    assert_eq!(eval(src), "done");
+}
+
+#[test]
+fn streams_are_lazy() {
+    let src = "(define (integers-from n) (cons-stream n (integers-from (+ n 1))))
+               (define (stream-ref s n)
+                 (if (= n 0) (stream-car s) (stream-ref (stream-cdr s) (- n 1))))
+               (define nat (integers-from 0))
+               (list (stream-car nat) (stream-car (stream-cdr nat)) (stream-ref nat 50))";
+    assert_eq!(eval(src), "(0 1 50)");
+}
+
+#[test]
+fn stream_tails_are_memoized() {
+    let mut interp = Interpreter::new();
+    let port = Port::output_string();
+    interp.set_output_port(port.clone());
+    interp
+        .eval_str(
+            "(define (tail) (display \"tail \") '())
+             (define s (cons-stream 1 (tail)))
+             (stream-cdr s) (stream-cdr s)",
+        )
+        .unwrap();
+    assert_eq!(port.output_string_contents().unwrap(), "tail ");
+}
@@ -0,0 +1,71 @@
+use rusty_scheme::port::Port;
+use rusty_scheme::Interpreter;
+use std::io::{self, Read};
+
+fn eval(src: &str) -> String {
+    Interpreter::new().eval_str(src).unwrap().to_string()
+}
+
+#[test]
+fn read_parses_successive_data() {
+    let src = r#"(define p (open-input-string "(a (b . c)) 42 \"s\" #t sym ()"))
+                 (list (read p) (read p) (read p) (read p) (read p) (read p)
+                       (eof-object? (read p)))"#;
+    assert_eq!(eval(src), "((a (b . c)) 42 \"s\" #t sym () #t)");
+}
+
+#[test]
+fn read_data_is_quoted() {
+    let src = "(define d (read (open-input-string \"(+ 1 2)\")))
+               (list (car d) (car (cdr d)) (symbol? (car d)))";
+    assert_eq!(eval(src), "(+ 1 #t)");
+    assert_eq!(eval("(read (open-input-string \"'x\"))"), "(quote x)");
+}
+
+#[test]
+fn read_skips_comments_and_whitespace() {
+    let src = "(define p (open-input-string \"  ; a comment\n  (1 2) ; another\n\"))
+               (list (read p) (eof-object? (read p)))";
+    assert_eq!(eval(src), "((1 2) #t)");
+}
+
+#[test]
+fn malformed_data_is_a_catchable_error() {
+    let src = "(guard (e ((error-object? e) 'malformed))
+                 (read (open-input-string \"(1 2\")))";
+    assert_eq!(eval(src), "malformed");
+    let err = Interpreter::new()
+        .eval_str("(read (open-input-string \")\"))")
+        .unwrap_err();
+    assert_eq!(err.code(), "read-error");
+}
+
+// Hands out one byte per call, so a datum arrives in pieces.
+struct Trickle(io::Cursor<&'static str>);
+
+impl Read for Trickle {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(1);
+        self.0.read(&mut buf[..n])
+    }
+}
+
+#[test]
+fn read_consumes_a_stream_incrementally() {
+    let mut interp = Interpreter::new();
+    let stream = Trickle(io::Cursor::new(
+        "(config (port . 8080))\n(hosts \"a\")\nrest",
+    ));
+    interp.set_input_port(Port::from_reader(stream));
+    assert_eq!(
+        interp.eval_str("(read)").unwrap().to_string(),
+        "(config (port . 8080))"
+    );
+    assert_eq!(
+        interp
+            .eval_str("(list (read) (read-line) (read-line))")
+            .unwrap()
+            .to_string(),
+        "((hosts \"a\") \"\" \"rest\")"
+    );
+}
@@ -0,0 +1,156 @@
+use std::fs;
+use std::process::{Command, Output};
+
+// Runs `scm` with `args` on a file holding `src`.
+fn scm(args: &[&str], src: &str) -> Output {
+    let path = std::env::temp_dir().join(format!(
+        "rusty-scheme-scm-{}-{}.scm",
+        std::process::id(),
+        args.join("")
+    ));
+    fs::write(&path, src).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_scm"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(path).unwrap();
+    output
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn normal_order_skips_unused_operands() {
+    let src = "(L3 (define (loop) (loop)) (define (f x y) x) (f 1 (loop)))";
+    let output = scm(&["--normal"], src);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "Result: 1\n");
+}
+
+#[test]
+fn trace_prints_each_step() {
+    let src = "(L3 (define (sq x) (* x x)) (sq (sq 2)))";
+    let output = scm(&["--trace"], src);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "operand: (sq 2)\n  substitute: (* 2 2)\nsubstitute: (* 4 4)\nResult: 16\n"
+    );
+}
+
+#[test]
+fn trace_cannot_be_combined_with_diagrams() {
+    let output = scm(&["--trace", "--dot"], "(L3 1)");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn dot_prints_a_diagram_per_breakpoint() {
+    let output = scm(&["--dot"], "(L3 (define x 1) (breakpoint 'here) x)");
+    assert!(output.status.success());
+    let out = stdout(&output);
+    assert_eq!(out.matches("digraph environment {").count(), 2);
+    assert!(out.contains("label=\"here\";"));
+    assert!(out.ends_with("}\nResult: 1\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn ctrl_c_interrupts_the_running_program() {
+    use std::process::Stdio;
+    use std::thread;
+    use std::time::Duration;
+
+    let path =
+        std::env::temp_dir().join(format!("rusty-scheme-scm-{}-int.scm", std::process::id()));
+    fs::write(&path, "(L3 (define (loop) (loop)) (loop))").unwrap();
+    let child = Command::new(env!("CARGO_BIN_EXE_scm"))
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_millis(300));
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(path).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Error [interrupted]"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn a_second_ctrl_c_exits_while_waiting_for_input() {
+    use std::process::Stdio;
+    use std::thread;
+    use std::time::Duration;
+
+    let path =
+        std::env::temp_dir().join(format!("rusty-scheme-scm-{}-read.scm", std::process::id()));
+    fs::write(&path, "(L3 (read-line))").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scm"))
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Keep stdin open, so that `read-line` blocks.
+    let _stdin = child.stdin.take();
+    for _ in 0..2 {
+        thread::sleep(Duration::from_millis(300));
+        let status = Command::new("kill")
+            .args(["-INT", &child.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(path).unwrap();
+    assert_eq!(output.status.code(), Some(130));
+}
+
+#[test]
+fn errors_print_a_backtrace_to_stderr() {
+    let src = "(L3\n(define (first l) (car l))\n(first 5))";
+    let output = scm(&[], src);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error [type-mismatch]: car: expected a pair, got 5
+    at (car 5) (2:19)
+    at (first 5) (3:1)
+"
+    );
+}
+
+#[test]
+fn parse_errors_print_their_position() {
+    let output = scm(&[], "(L3\n(car 1)");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error [parse-error]: Parse error: unexpected end of input\n    at 2:8\n"
+    );
+}
+
+#[test]
+fn undefined_variables_print_their_suggestions() {
+    let src = "(L3 (define (square x) (* x x)) (squre 3))";
+    let output = scm(&[], src);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error [undefined-variable]: Undefined variable: squre (did you mean square?)\n"
+    );
+}
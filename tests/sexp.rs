@@ -0,0 +1,157 @@
+#![cfg(feature = "serde")]
+
+use rusty_scheme::{from_str, to_string};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Config {
+    port: u16,
+    hosts: Vec<String>,
+    mode: Mode,
+    verbose: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Mode {
+    Plain,
+    Tls { cert: String },
+    Proxy(String, u16),
+}
+
+fn round_trip<T>(value: &T) -> T
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    from_str(&to_string(value).unwrap()).unwrap()
+}
+
+#[test]
+fn structs_are_association_lists() {
+    let config = Config {
+        port: 8080,
+        hosts: vec!["a".to_owned(), "b".to_owned()],
+        mode: Mode::Tls {
+            cert: "c.pem".to_owned(),
+        },
+        verbose: Some(true),
+    };
+    let text = to_string(&config).unwrap();
+    assert_eq!(
+        text,
+        r#"((port . 8080) (hosts "a" "b") (mode Tls (cert . "c.pem")) (verbose . #t))"#
+    );
+    assert_eq!(from_str::<Config>(&text).unwrap(), config);
+}
+
+#[test]
+fn none_fields_are_left_out() {
+    let config = Config {
+        port: 1,
+        hosts: vec![],
+        mode: Mode::Plain,
+        verbose: None,
+    };
+    assert_eq!(
+        to_string(&config).unwrap(),
+        "((port . 1) (hosts) (mode . Plain))"
+    );
+}
+
+#[test]
+fn option_bool_fields_round_trip() {
+    for verbose in [None, Some(false), Some(true)] {
+        let config = Config {
+            port: 1,
+            hosts: vec![],
+            mode: Mode::Proxy("p".to_owned(), 3128),
+            verbose,
+        };
+        assert_eq!(round_trip(&config), config);
+    }
+}
+
+#[test]
+fn false_outside_fields_is_none() {
+    assert_eq!(to_string(&vec![Some(1), None]).unwrap(), "(1 #f)");
+    assert_eq!(
+        from_str::<Vec<Option<u8>>>("(1 #f)").unwrap(),
+        [Some(1), None]
+    );
+}
+
+#[test]
+fn maps_and_tuples_round_trip() {
+    let mut map = BTreeMap::new();
+    map.insert("a".to_owned(), (1, 'x'));
+    map.insert("b".to_owned(), (2, 'y'));
+    assert_eq!(round_trip(&map), map);
+    assert_eq!(round_trip(&Mode::Plain), Mode::Plain);
+}
+
+#[test]
+fn non_finite_floats_round_trip() {
+    let floats = vec![f64::INFINITY, f64::NEG_INFINITY, 1.5];
+    assert_eq!(to_string(&floats).unwrap(), "(+inf.0 -inf.0 1.5)");
+    assert_eq!(round_trip(&floats), floats);
+    let nan: Vec<f64> = from_str(&to_string(&vec![f64::NAN]).unwrap()).unwrap();
+    assert!(nan[0].is_nan());
+}
+
+#[derive(Debug, Deserialize)]
+struct Server {
+    #[allow(dead_code)]
+    port: u16,
+}
+
+#[test]
+fn errors_name_the_failing_field() {
+    let err = from_str::<Server>(r#"((port . "x"))"#).unwrap_err();
+    assert_eq!(err.to_string(), r#"field port: expected a number, got "x""#);
+    assert_eq!(err.path(), ["field port"]);
+    let source = std::error::Error::source(&err).unwrap();
+    assert_eq!(source.to_string(), r#"expected a number, got "x""#);
+}
+
+#[test]
+fn errors_name_the_failing_element_and_key() {
+    let err = from_str::<Vec<Server>>(r#"(((port . 1)) ((port . #t)))"#).unwrap_err();
+    assert_eq!(err.path(), ["element 1", "field port"]);
+    let err = from_str::<BTreeMap<u8, u8>>(r#"((1 . 2) ("a" . 3))"#).unwrap_err();
+    assert_eq!(err.path(), ["key"]);
+    let err = from_str::<BTreeMap<String, u8>>(r#"(("a" . 1) ("b" . "c"))"#).unwrap_err();
+    assert_eq!(err.path(), [r#""b""#]);
+}
+
+#[test]
+fn malformed_text_reports_the_position() {
+    let src = "((port . 1)\n (hosts \"a\" . ))";
+    let err = from_str::<Config>(src).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Parse error: unexpected character ')' at 2:15"
+    );
+    assert_eq!(err.span().unwrap().line_col(src), (2, 15));
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn the_configuration_example_reads() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Server {
+        port: u16,
+        hosts: Vec<String>,
+    }
+    let server: Server = from_str(r#"((port . 8080) (hosts "a" "b"))"#).unwrap();
+    assert_eq!(
+        server,
+        Server {
+            port: 8080,
+            hosts: vec!["a".to_owned(), "b".to_owned()],
+        }
+    );
+    assert_eq!(
+        to_string(&server).unwrap(),
+        r#"((port . 8080) (hosts "a" "b"))"#
+    );
+}
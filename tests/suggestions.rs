@@ -0,0 +1,51 @@
+use rusty_scheme::{Interpreter, SchemeError};
+
+fn suggestions(src: &str) -> Vec<String> {
+    match Interpreter::new().eval_str(src).unwrap_err().root() {
+        SchemeError::UndefinedVariable { suggestions, .. } => suggestions.clone(),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn defined_names_are_suggested() {
+    let src = "(define (sqrt-iter guess x) guess)
+               (sqrt-itr 1 2)";
+    assert_eq!(suggestions(src), ["sqrt-iter"]);
+    let src = "(define (lenght l) 0) (define (length l) 0) (lentgh '())";
+    assert_eq!(suggestions(src), ["length", "lenght"]);
+}
+
+#[test]
+fn primitive_names_are_suggested() {
+    assert_eq!(suggestions("(dispaly 1)"), ["display"]);
+    assert_eq!(suggestions("(cadr '(1 2))"), ["car", "cdr"]);
+}
+
+#[test]
+fn unlike_names_get_no_suggestion() {
+    assert!(suggestions("(define (add a b) (+ a b)) (frobnicate 1)").is_empty());
+    assert_eq!(
+        Interpreter::new()
+            .eval_str("frobnicate")
+            .unwrap_err()
+            .to_string(),
+        "Undefined variable: frobnicate"
+    );
+}
+
+#[test]
+fn the_message_lists_the_suggestions() {
+    let mut interp = Interpreter::new();
+    interp
+        .eval_str("(define (add a b) (+ a b)) (define (and3 a b c) (and a (and b c))) 'ok")
+        .unwrap();
+    assert_eq!(
+        interp.eval_str("(addd 1 2)").unwrap_err().to_string(),
+        "Undefined variable: addd (did you mean add?)"
+    );
+    assert_eq!(
+        interp.eval_str("(ad 1 2)").unwrap_err().to_string(),
+        "Undefined variable: ad (did you mean add or and?)"
+    );
+}
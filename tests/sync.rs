@@ -0,0 +1,74 @@
+#![cfg(feature = "sync")]
+
+use rusty_scheme::value::Value;
+use rusty_scheme::Interpreter;
+use std::sync::Arc;
+use std::thread;
+
+const LIBRARY: &str = "(define (fib n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2)))))
+                       (define table '((a . 1) (b . 2)))";
+
+#[test]
+fn a_loaded_interpreter_is_evaluated_from_several_threads() {
+    let mut library = Interpreter::new();
+    library.eval_str(LIBRARY).unwrap();
+    let workers: Vec<_> = (0..4)
+        .map(|i| {
+            let mut interp = library.clone();
+            thread::spawn(move || {
+                interp
+                    .eval_str(&format!("(define mine {}) (fib (+ 10 mine))", i))
+                    .unwrap()
+                    .to_string()
+            })
+        })
+        .collect();
+    let results: Vec<_> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+    assert_eq!(results, ["55", "89", "144", "233"]);
+    // Definitions made by the workers stay in their clones.
+    assert!(library.get_global("mine").is_none());
+}
+
+#[test]
+fn values_are_shared_between_threads() {
+    let mut interp = Interpreter::new();
+    interp.eval_str(LIBRARY).unwrap();
+    let table = Arc::new(interp.get_global("table").unwrap());
+    let fib = interp.get_global("fib").unwrap();
+    let workers: Vec<_> = (0..2)
+        .map(|_| {
+            let table = Arc::clone(&table);
+            let fib = fib.clone();
+            thread::spawn(move || {
+                let sum = Interpreter::apply(&fib, &[Value::Number(12.0)]).unwrap();
+                (table.to_string(), sum.to_string())
+            })
+        })
+        .collect();
+    for worker in workers {
+        assert_eq!(
+            worker.join().unwrap(),
+            ("((a . 1) (b . 2))".to_owned(), "144".to_owned())
+        );
+    }
+}
+
+#[test]
+fn host_functions_can_be_called_from_any_thread() {
+    let mut interp = Interpreter::new();
+    let offset = Arc::new(100.0);
+    interp.register_typed::<(f64,), f64>("offset", move |(n,)| n + *offset);
+    let workers: Vec<_> = (0..3)
+        .map(|i| {
+            let mut interp = interp.clone();
+            thread::spawn(move || {
+                interp
+                    .eval_str(&format!("(offset {})", i))
+                    .unwrap()
+                    .to_string()
+            })
+        })
+        .collect();
+    let results: Vec<_> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+    assert_eq!(results, ["100", "101", "102"]);
+}
@@ -0,0 +1,88 @@
+use rusty_scheme::trace::{StepKind, TraceStep};
+use rusty_scheme::{eval_program_traced, parse_program, EvaluationOrder};
+
+fn trace(src: &str, order: EvaluationOrder) -> (String, Vec<TraceStep>) {
+    let (_, program) = parse_program(src).unwrap();
+    let mut steps = vec![];
+    let value = eval_program_traced(&program, order, &mut |step| steps.push(step.clone())).unwrap();
+    (value.to_string(), steps)
+}
+
+fn kinds_and_expressions(steps: &[TraceStep]) -> Vec<(StepKind, &str)> {
+    steps
+        .iter()
+        .map(|step| (step.kind, step.expression.as_str()))
+        .collect()
+}
+
+#[test]
+fn operands_and_substitutions_are_recorded() {
+    let (value, steps) = trace(
+        "(L3 (define (sq x) (* x x)) (sq (sq 2)))",
+        EvaluationOrder::Applicative,
+    );
+    assert_eq!(value, "16");
+    assert_eq!(
+        kinds_and_expressions(&steps),
+        [
+            (StepKind::Operand, "(sq 2)"),
+            (StepKind::Substitute, "(* 2 2)"),
+            (StepKind::Substitute, "(* 4 4)"),
+        ]
+    );
+    assert!(steps[1].depth > steps[2].depth);
+}
+
+#[test]
+fn if_selection_is_recorded() {
+    let (value, steps) = trace(
+        "(L3 (define (sq x) (* x x)) (if (< 1 2) (sq 3) 0))",
+        EvaluationOrder::Applicative,
+    );
+    assert_eq!(value, "9");
+    assert_eq!(
+        kinds_and_expressions(&steps),
+        [(StepKind::If, "(sq 3)"), (StepKind::Substitute, "(* 3 3)")]
+    );
+}
+
+#[test]
+fn renaming_is_recorded_before_substitution() {
+    let (value, steps) = trace(
+        "(L3 (define (f x) (lambda (y) (+ x y))) (define y 5) ((f y) 1))",
+        EvaluationOrder::Applicative,
+    );
+    assert_eq!(value, "6");
+    assert_eq!(
+        kinds_and_expressions(&steps),
+        [
+            (StepKind::Operand, "y"),
+            (StepKind::Rename, "(lambda (y__1) (+ x y__1))"),
+            (StepKind::Substitute, "(lambda (y__1) (+ 5 y__1))"),
+            (StepKind::Substitute, "(+ 5 1)"),
+        ]
+    );
+}
+
+#[test]
+fn normal_order_substitutes_unevaluated_operands() {
+    let (value, steps) = trace(
+        "(L3 (define (f x y) x) (f (+ 1 1) (* 2 2)))",
+        EvaluationOrder::Normal,
+    );
+    assert_eq!(value, "2");
+    assert_eq!(
+        kinds_and_expressions(&steps),
+        [(StepKind::Substitute, "(+ 1 1)")]
+    );
+}
+
+#[test]
+fn steps_are_indented_by_depth() {
+    let step = TraceStep {
+        kind: StepKind::Substitute,
+        depth: 2,
+        expression: "(* 2 2)".to_owned(),
+    };
+    assert_eq!(step.to_string(), "    substitute: (* 2 2)");
+}
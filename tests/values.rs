@@ -0,0 +1,109 @@
+use rusty_scheme::value::Value;
+use rusty_scheme::Interpreter;
+
+fn eval(interp: &mut Interpreter, src: &str) -> String {
+    interp.eval_str(src).unwrap().to_string()
+}
+
+fn error_code(src: &str) -> &'static str {
+    Interpreter::new().eval_str(src).unwrap_err().code()
+}
+
+#[test]
+fn call_with_values_spreads_the_values() {
+    let mut interp = Interpreter::new();
+    assert_eq!(
+        eval(&mut interp, "(call-with-values (lambda () (values 1 2)) +)"),
+        "3"
+    );
+    assert_eq!(
+        eval(&mut interp, "(call-with-values (lambda () 5) list)"),
+        "(5)"
+    );
+    assert_eq!(eval(&mut interp, "(call-with-values values list)"), "()");
+}
+
+#[test]
+fn receive_and_let_values_bind_each_value() {
+    let mut interp = Interpreter::new();
+    assert_eq!(
+        eval(&mut interp, "(receive (q r) (values 3 1) (list q r))"),
+        "(3 1)"
+    );
+    assert_eq!(
+        eval(
+            &mut interp,
+            "(let-values (((a b) (values 1 2)) ((c) (values 3))) (list a b c))"
+        ),
+        "(1 2 3)"
+    );
+}
+
+#[test]
+fn let_values_binds_in_parallel_and_let_star_values_in_sequence() {
+    let mut interp = Interpreter::new();
+    assert_eq!(
+        eval(
+            &mut interp,
+            "(let ((a 'outer)) (let-values (((a) (values 1)) ((b) (values a))) (list a b)))"
+        ),
+        "(1 outer)"
+    );
+    assert_eq!(
+        eval(
+            &mut interp,
+            "(let ((a 'outer)) (let*-values (((a) (values 1)) ((b) (values a))) (list a b)))"
+        ),
+        "(1 1)"
+    );
+}
+
+#[test]
+fn define_values_defines_each_name() {
+    let mut interp = Interpreter::new();
+    interp
+        .eval_str("(define-values (x y) (values 1 2))")
+        .unwrap();
+    assert_eq!(eval(&mut interp, "(list x y)"), "(1 2)");
+}
+
+#[test]
+fn a_single_value_is_an_ordinary_value() {
+    let mut interp = Interpreter::new();
+    assert_eq!(eval(&mut interp, "(+ 1 (values 2))"), "3");
+    assert!(matches!(
+        Value::values(vec![Value::Number(1.0)]),
+        Value::Number(_)
+    ));
+    assert_eq!(
+        Value::values(vec![Value::Number(1.0), Value::Boolean(true)])
+            .into_values()
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn the_number_of_values_must_match() {
+    assert_eq!(
+        error_code("(let-values (((a b) (values 1))) a)"),
+        "values-mismatch"
+    );
+    assert_eq!(
+        error_code("(receive (a) (values 1 2) a)"),
+        "values-mismatch"
+    );
+    assert_eq!(
+        error_code("(define-values (x y z) (values 1 2))"),
+        "values-mismatch"
+    );
+    let err = Interpreter::new()
+        .eval_str("(let-values (((a b) (values 1))) a)")
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Expected 2 values, got 1");
+}
+
+#[test]
+fn several_values_are_not_one() {
+    assert_eq!(error_code("(+ (values 1 2) 1)"), "type-mismatch");
+}